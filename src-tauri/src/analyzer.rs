@@ -0,0 +1,255 @@
+use crate::cleaner::{allocated_size, file_id, link_count, FileId};
+use crate::error::CleanerError;
+use crate::jobs::Job;
+use crate::walker::{self, Visit, WalkEntry};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+
+/// Имя, под которым анализ отображается в событиях прогресса
+const USAGE_CATEGORY: &str = "disk_usage";
+
+/// Узел дерева занятого места. У каталога размеры и счётчики включают всё вложенное
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageNode {
+    pub name: String,
+    pub path: String,
+    /// Место на диске; жёсткие ссылки на один файл считаются один раз
+    pub size: u64,
+    /// Видимая длина
+    pub apparent_size: u64,
+    pub files: u64,
+    pub dirs: u64,
+    pub is_directory: bool,
+    /// Самые большие дочерние элементы по убыванию размера
+    pub children: Vec<UsageNode>,
+    /// Дочерние элементы, не вошедшие в `children` из-за ограничений глубины и числа потомков
+    pub hidden_children: u64,
+    pub hidden_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub root: UsageNode,
+    /// Точки монтирования внутри корня, которые не анализировались из-за `one_file_system`
+    pub skipped_mounts: Vec<String>,
+    pub analyzed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UsageOptions {
+    /// Сколько уровней дерева вернуть. Размеры всё равно считаются по всей глубине
+    pub max_depth: usize,
+    /// Сколько самых больших потомков вернуть на каждом уровне
+    pub max_children: usize,
+    pub one_file_system: bool,
+}
+
+impl Default for UsageOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            max_children: 32,
+            one_file_system: true,
+        }
+    }
+}
+
+/// Элемент дерева, найденный обходом или прочитанный из выгрузки ncdu
+pub(crate) enum Item {
+    Dir { path: PathBuf, allocated: u64, apparent: u64 },
+    File { path: PathBuf, allocated: u64, apparent: u64, id: Option<FileId>, links: u64 },
+    Mount(PathBuf),
+}
+
+/// Узел до свёртки в дерево: размеры пока только собственные
+struct RawNode {
+    path: PathBuf,
+    is_directory: bool,
+    size: u64,
+    apparent_size: u64,
+    files: u64,
+    dirs: u64,
+    children: Vec<usize>,
+}
+
+/// Считает занятое место в дереве `root`. Обход идёт на текущем пуле rayon тем же обходчиком,
+/// что и сканирование категорий; символические ссылки не раскрываются
+pub fn analyze(root: &Path, options: &UsageOptions, job: &Job) -> Result<UsageReport, CleanerError> {
+    let root_metadata = std::fs::metadata(root).map_err(|_| CleanerError::InvalidPath {
+        path: root.to_string_lossy().to_string(),
+    })?;
+    let root_device = device(&root_metadata);
+
+    let visit = |entry: &WalkEntry, found: &mut Vec<Item>| {
+        if job.is_cancelled() {
+            return Visit::SkipDir;
+        }
+
+        let metadata = &entry.metadata;
+        let allocated = allocated_size(metadata);
+
+        if !metadata.is_dir() {
+            job.file_seen(USAGE_CATEGORY, entry.path.parent().unwrap_or(root));
+            job.file_matched(allocated);
+            found.push(Item::File {
+                path: entry.path.clone(),
+                allocated,
+                apparent: metadata.len(),
+                id: file_id(metadata),
+                links: link_count(metadata),
+            });
+            return Visit::Continue;
+        }
+
+        job.dir_seen(USAGE_CATEGORY, &entry.path);
+        if options.one_file_system && entry.depth > 0 && device(metadata) != root_device {
+            found.push(Item::Mount(entry.path.clone()));
+            return Visit::SkipDir;
+        }
+
+        found.push(Item::Dir {
+            path: entry.path.clone(),
+            allocated,
+            apparent: metadata.len(),
+        });
+        Visit::Continue
+    };
+    let items = walker::walk(root, usize::MAX, visit, |_, _, _| job.error_seen());
+
+    if job.is_cancelled() {
+        return Err(CleanerError::Cancelled);
+    }
+
+    build_report(items, options).ok_or_else(|| CleanerError::InvalidPath {
+        path: root.to_string_lossy().to_string(),
+    })
+}
+
+/// Собирает дерево из элементов. Корень — элемент с самым коротким путём, остальные цепляются к нему
+pub(crate) fn build_report(mut items: Vec<Item>, options: &UsageOptions) -> Option<UsageReport> {
+    let mut skipped_mounts = Vec::new();
+    let mut nodes: Vec<RawNode> = Vec::new();
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    // Покомпонентная сортировка ставит каталог раньше его содержимого
+    items.sort_by(|a, b| item_path(a).cmp(item_path(b)));
+
+    for item in items {
+        let node = match item {
+            Item::Mount(path) => {
+                skipped_mounts.push(path.to_string_lossy().to_string());
+                continue;
+            }
+            Item::Dir { path, allocated, apparent } => RawNode {
+                path,
+                is_directory: true,
+                size: allocated,
+                apparent_size: apparent,
+                files: 0,
+                dirs: 0,
+                children: Vec::new(),
+            },
+            Item::File { path, allocated, apparent, id, links } => {
+                // Повторная жёсткая ссылка места не занимает
+                let counted = match id {
+                    Some(id) if links > 1 => seen.insert(id),
+                    _ => true,
+                };
+                RawNode {
+                    path,
+                    is_directory: false,
+                    size: if counted { allocated } else { 0 },
+                    apparent_size: if counted { apparent } else { 0 },
+                    files: 1,
+                    dirs: 0,
+                    children: Vec::new(),
+                }
+            }
+        };
+
+        let position = nodes.len();
+        if let Some(parent) = node.path.parent().and_then(|parent| index.get(parent)) {
+            nodes[*parent].children.push(position);
+        } else if position > 0 {
+            // Родитель не попал в дерево (например, корень — файл); такой элемент не к чему прицепить
+            continue;
+        }
+        if node.is_directory {
+            index.insert(node.path.clone(), position);
+        }
+        nodes.push(node);
+    }
+
+    if nodes.is_empty() {
+        return None;
+    }
+
+    // Потомки всегда стоят после родителя, поэтому суммы собираются проходом с конца
+    for position in (0..nodes.len()).rev() {
+        let (size, apparent_size, files, dirs) = nodes[position].children.iter().fold((0, 0, 0, 0), |acc, &child| {
+            let child = &nodes[child];
+            (
+                acc.0 + child.size,
+                acc.1 + child.apparent_size,
+                acc.2 + child.files,
+                acc.3 + child.dirs + u64::from(child.is_directory),
+            )
+        });
+        let node = &mut nodes[position];
+        node.size += size;
+        node.apparent_size += apparent_size;
+        node.files += files;
+        node.dirs += dirs;
+    }
+
+    skipped_mounts.sort();
+    Some(UsageReport {
+        root: build_node(&nodes, 0, 0, options),
+        skipped_mounts,
+        analyzed_at: Utc::now(),
+    })
+}
+
+fn item_path(item: &Item) -> &Path {
+    match item {
+        Item::Dir { path, .. } | Item::File { path, .. } | Item::Mount(path) => path,
+    }
+}
+
+/// Сворачивает узел в дерево ответа, оставляя на каждом уровне только самых больших потомков
+fn build_node(nodes: &[RawNode], position: usize, depth: usize, options: &UsageOptions) -> UsageNode {
+    let raw = &nodes[position];
+
+    let mut children = raw.children.clone();
+    children.sort_by(|&a, &b| nodes[b].size.cmp(&nodes[a].size).then_with(|| nodes[a].path.cmp(&nodes[b].path)));
+
+    let shown = if depth < options.max_depth { options.max_children.min(children.len()) } else { 0 };
+    let hidden = &children[shown..];
+
+    UsageNode {
+        name: raw
+            .path
+            .file_name()
+            .map_or_else(|| raw.path.to_string_lossy().to_string(), |name| name.to_string_lossy().to_string()),
+        path: raw.path.to_string_lossy().to_string(),
+        size: raw.size,
+        apparent_size: raw.apparent_size,
+        files: raw.files,
+        dirs: raw.dirs,
+        is_directory: raw.is_directory,
+        children: children[..shown]
+            .iter()
+            .map(|&child| build_node(nodes, child, depth + 1, options))
+            .collect(),
+        hidden_children: hidden.len() as u64,
+        hidden_size: hidden.iter().map(|&child| nodes[child].size).sum(),
+    }
+}
+
+fn device(metadata: &Metadata) -> Option<u64> {
+    file_id(metadata).map(|id| id.device)
+}
@@ -0,0 +1,310 @@
+use std::path::{Path, PathBuf};
+
+/// Подкаталоги профиля Chromium, где лежит только кеш
+const CHROMIUM_CACHE_DIRS: [&str; 3] = ["Cache", "Code Cache", "GPUCache"];
+
+/// Подкаталоги профиля Firefox, где лежит только кеш
+const FIREFOX_CACHE_DIRS: [&str; 2] = ["cache2", "startupCache"];
+
+/// Расположение браузера: каталог с профилями и каталог, куда браузер выносит кеш тех же профилей
+/// (на Windows это `Local` вместо `Roaming`, на Linux `~/.cache`, на macOS `~/Library/Caches`)
+struct Install {
+    data: PathBuf,
+    cache: Option<PathBuf>,
+    /// Opera хранит единственный профиль прямо в каталоге данных
+    single_profile: bool,
+}
+
+/// Каталоги кеша всех профилей установленных браузеров. Профили берутся из `Local State`
+/// (Chrome, Chromium, Edge, Brave, Vivaldi, Opera) и `profiles.ini` (Firefox); закладки, пароли
+/// и cookies лежат рядом в тех же профилях, поэтому берём только подкаталоги кеша
+pub fn cache_paths() -> Vec<PathBuf> {
+    let mut paths = vec![];
+
+    for install in chromium_installs() {
+        let profiles = if install.single_profile {
+            vec![PathBuf::new()]
+        } else {
+            chromium_profiles(&install.data)
+        };
+        paths.extend(profile_cache_dirs(&install, &profiles, &CHROMIUM_CACHE_DIRS));
+    }
+
+    for install in firefox_installs() {
+        let profiles = firefox_profiles(&install.data);
+        paths.extend(profile_cache_dirs(&install, &profiles, &FIREFOX_CACHE_DIRS));
+    }
+
+    // На Windows каталог кеша Chromium совпадает с каталогом данных
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Существующие каталоги кеша профилей: и внутри профиля, и в его зеркале в каталоге кеша.
+/// Пути профилей здесь относительные; абсолютный путь (профиль Firefox вне каталога данных)
+/// при `join` заменяет базу, и зеркало совпадает с самим профилем
+fn profile_cache_dirs(install: &Install, profiles: &[PathBuf], cache_dirs: &[&str]) -> Vec<PathBuf> {
+    let mut paths = vec![];
+
+    for profile in profiles {
+        let roots = Some(&install.data).into_iter().chain(install.cache.as_ref());
+        for root in roots {
+            let profile_dir = root.join(profile);
+            paths.extend(
+                cache_dirs
+                    .iter()
+                    .map(|name| profile_dir.join(name))
+                    .filter(|path| is_real_dir(path)),
+            );
+        }
+    }
+
+    paths
+}
+
+/// Профили Chromium из `profile.info_cache` в `Local State`. Профиль `Default` есть всегда,
+/// даже если файл не читается
+fn chromium_profiles(data: &Path) -> Vec<PathBuf> {
+    let mut profiles = vec![PathBuf::from("Default")];
+
+    let local_state = std::fs::read_to_string(data.join("Local State"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+
+    if let Some(info_cache) = local_state
+        .as_ref()
+        .and_then(|state| state["profile"]["info_cache"].as_object())
+    {
+        profiles.extend(
+            info_cache
+                .keys()
+                .filter(|name| is_plain_name(name))
+                .map(PathBuf::from),
+        );
+    }
+
+    profiles.sort();
+    profiles.dedup();
+    profiles
+}
+
+/// Профили Firefox из секций `[ProfileN]` в `profiles.ini`. Относительные пути считаются от
+/// каталога с `profiles.ini`, абсолютные берутся как есть
+fn firefox_profiles(data: &Path) -> Vec<PathBuf> {
+    let content = match std::fs::read_to_string(data.join("profiles.ini")) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+
+    let mut profiles = vec![];
+    let mut section = IniProfile::default();
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            profiles.extend(section.finish());
+            section = IniProfile {
+                is_profile: line.starts_with("[Profile"),
+                ..Default::default()
+            };
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some(("Path", value)) => section.path = Some(value.trim().to_string()),
+            Some(("IsRelative", value)) => section.is_relative = value.trim() != "0",
+            _ => {}
+        }
+    }
+    profiles.extend(section.finish());
+
+    profiles
+}
+
+#[derive(Default)]
+struct IniProfile {
+    is_profile: bool,
+    path: Option<String>,
+    is_relative: bool,
+}
+
+impl IniProfile {
+    fn finish(&self) -> Option<PathBuf> {
+        let path = PathBuf::from(self.path.as_ref().filter(|_| self.is_profile)?);
+
+        // Относительный путь не должен выводить за пределы каталога Firefox
+        if self.is_relative {
+            path.components()
+                .all(|component| matches!(component, std::path::Component::Normal(_)))
+                .then_some(path)
+        } else {
+            path.is_absolute().then_some(path)
+        }
+    }
+}
+
+/// Имя профиля из `Local State` — один компонент пути, без `..` и разделителей
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    )
+}
+
+/// Каталог, но не символическая ссылка на него: по ссылке кеш мог бы оказаться где угодно
+fn is_real_dir(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+}
+
+#[cfg(windows)]
+fn chromium_installs() -> Vec<Install> {
+    let local = match dirs::data_local_dir() {
+        Some(local) => local,
+        None => return vec![],
+    };
+
+    let mut installs: Vec<Install> = [
+        "Google\\Chrome\\User Data",
+        "Chromium\\User Data",
+        "Microsoft\\Edge\\User Data",
+        "BraveSoftware\\Brave-Browser\\User Data",
+        "Vivaldi\\User Data",
+    ]
+    .iter()
+    .map(|relative| Install {
+        data: local.join(relative),
+        cache: None,
+        single_profile: false,
+    })
+    .collect();
+
+    // Профиль Opera лежит в Roaming, а кеш — в таком же каталоге в Local
+    if let Some(roaming) = dirs::data_dir() {
+        installs.push(Install {
+            data: roaming.join("Opera Software\\Opera Stable"),
+            cache: Some(local.join("Opera Software\\Opera Stable")),
+            single_profile: true,
+        });
+    }
+
+    installs
+}
+
+#[cfg(not(windows))]
+fn chromium_installs() -> Vec<Install> {
+    let (config, cache) = match (dirs::config_dir(), dirs::cache_dir()) {
+        (Some(config), Some(cache)) => (config, cache),
+        _ => return vec![],
+    };
+
+    let relatives: [(&str, bool); 6] = if cfg!(target_os = "macos") {
+        [
+            ("Google/Chrome", false),
+            ("Chromium", false),
+            ("Microsoft Edge", false),
+            ("BraveSoftware/Brave-Browser", false),
+            ("Vivaldi", false),
+            ("com.operasoftware.Opera", true),
+        ]
+    } else {
+        [
+            ("google-chrome", false),
+            ("chromium", false),
+            ("microsoft-edge", false),
+            ("BraveSoftware/Brave-Browser", false),
+            ("vivaldi", false),
+            ("opera", true),
+        ]
+    };
+
+    relatives
+        .iter()
+        .map(|(relative, single_profile)| Install {
+            data: config.join(relative),
+            cache: Some(cache.join(relative)),
+            single_profile: *single_profile,
+        })
+        .collect()
+}
+
+fn firefox_installs() -> Vec<Install> {
+    let (data, cache) = if cfg!(windows) {
+        (
+            dirs::data_dir().map(|roaming| roaming.join("Mozilla").join("Firefox")),
+            dirs::data_local_dir().map(|local| local.join("Mozilla").join("Firefox")),
+        )
+    } else if cfg!(target_os = "macos") {
+        (
+            dirs::data_dir().map(|support| support.join("Firefox")),
+            dirs::cache_dir().map(|caches| caches.join("Firefox")),
+        )
+    } else {
+        (
+            dirs::home_dir().map(|home| home.join(".mozilla").join("firefox")),
+            dirs::cache_dir().map(|cache| cache.join("mozilla").join("firefox")),
+        )
+    };
+
+    data.map(|data| Install {
+        data,
+        cache,
+        single_profile: false,
+    })
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Абсолютный путь профиля в примере записан по-unix
+    #[cfg(unix)]
+    #[test]
+    fn firefox_profiles_keep_safe_paths_only() {
+        let data = std::env::temp_dir().join(format!("browsers-firefox-{}", std::process::id()));
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::write(
+            data.join("profiles.ini"),
+            "[General]\n\
+             StartWithLastProfile=1\n\
+             Path=ignored\n\
+             \n\
+             [Profile0]\n\
+             Name=default\n\
+             IsRelative=1\n\
+             Path=Profiles/abc.default\n\
+             \n\
+             [Profile1]\n\
+             IsRelative=0\n\
+             Path=/opt/firefox/profile\n\
+             \n\
+             [Profile2]\n\
+             IsRelative=1\n\
+             Path=../../escape\n\
+             \n\
+             [Profile3]\n\
+             IsRelative=0\n\
+             Path=relative/but/marked/absolute\n\
+             \n\
+             [Install4F96D1932A9F858E]\n\
+             Default=Profiles/abc.default\n",
+        )
+        .unwrap();
+
+        let profiles = firefox_profiles(&data);
+        assert_eq!(profiles, [PathBuf::from("Profiles/abc.default"), PathBuf::from("/opt/firefox/profile")]);
+
+        std::fs::remove_dir_all(&data).unwrap();
+        assert!(firefox_profiles(&data).is_empty());
+    }
+
+    #[test]
+    fn chromium_profile_names_are_single_components() {
+        assert!(is_plain_name("Profile 1"));
+        assert!(!is_plain_name("../Default"));
+        assert!(!is_plain_name("a/b"));
+        assert!(!is_plain_name(""));
+    }
+}
@@ -1,14 +1,30 @@
-use crate::config::{AppConfig, CleanupCategory};
+use crate::analyzer::{self, UsageOptions};
+use crate::config::{AgeTimestamp, AppConfig, CleanupCategory, SymlinkPolicy};
+use crate::duplicates::{self, DuplicateSet, PlannedRemovals};
 use crate::error::CleanerError;
+use crate::jobs::{Job, ScanEstimate};
+use crate::mounts::MountTable;
+use crate::ownership;
+use crate::patterns::PatternSet;
+use crate::quarantine::{self, QuarantineSession};
+use crate::safety::ProtectedPaths;
+use crate::scan_index::{self, CategoryIndex, DirRecord, IndexedFile, ScanIndex};
+use crate::shred;
+use crate::trash;
+use crate::walker::{self, CachedDir, DirCache, Visit, WalkEntry};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
-use std::time::{Duration, UNIX_EPOCH};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use walkdir::WalkDir;
 use std::io;
 
 /// Переводит системные ошибки на человеческий язык
-fn translate_error(error: &io::Error) -> String {
+pub(crate) fn translate_error(error: &io::Error) -> String {
     match error.raw_os_error() {
         Some(32) => "Файл используется другой программой".to_string(),
         Some(5) => "Нет доступа к файлу (требуются права администратора)".to_string(),
@@ -62,28 +78,290 @@ pub struct ScanResult {
     pub total_files: usize,
     pub total_size: u64,
     pub files: Vec<FileInfo>,
+    pub errors: Vec<String>,
+    /// Файлы, подошедшие под фильтры, но отброшенные из-за ограничений размера
+    pub skipped_by_size: usize,
+    pub skipped_by_size_bytes: u64,
+    /// Файлы, которые подошли категории, но достались другой категории с пересекающимся корнем
+    pub overlaps: Vec<CategoryOverlap>,
+    /// Поддеревья, в которые сканирование не спускалось из-за настроек обхода категории
+    pub cut_off: Vec<CutOff>,
+    /// Наборы дубликатов, если категория их ищет. В `files` тогда попадают только лишние копии
+    pub duplicates: Vec<DuplicateSet>,
+    /// Точка монтирования, на которой очистка освободит больше всего места
+    pub mount: Option<String>,
+    /// Когда началось сканирование: файлы, появившиеся позже, очистка не трогает, а показывает
+    #[serde(skip)]
+    pub(crate) scanned_at: Option<SystemTime>,
+}
+
+impl ScanResult {
+    /// Пересчитывает итоги по списку файлов. Место считается так же, как при пробной очистке:
+    /// один раз на inode и только если в список входят все жёсткие ссылки на него
+    pub(crate) fn recount(&mut self) {
+        let planned: PlannedRemovals = self.files.iter().collect();
+        self.total_files = self.files.len();
+        self.total_size = self.files.iter().map(|file| planned.reclaimed(file)).sum();
+    }
+}
+
+/// Итоги сразу по нескольким категориям
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanTotals {
+    pub total_files: usize,
+    /// Жёсткие ссылки на один файл из разных категорий считаются вместе, как при их общей очистке
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CutOff {
+    pub path: String,
+    pub reason: CutOffReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CutOffReason {
+    /// Достигнута `max_depth` категории
+    MaxDepth,
+    /// Ссылка на каталог при политике `skip`
+    Symlink,
+    /// Каталог уже открыт выше по пути: ссылка или bind-монтирование ведёт в одного из предков
+    SymlinkLoop,
+    /// Другая файловая система при `one_file_system`
+    OtherFilesystem,
+}
+
+/// Сколько найденного категорией отдано категории-владельцу
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryOverlap {
+    pub owner: String,
+    pub files: usize,
+    pub size: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
     pub path: String,
+    /// Видимая длина файла
     pub size: u64,
+    /// Место, которое файл занимает на диске. У каталога — то, что освободится при его удалении
+    #[serde(default)]
+    pub allocated: u64,
+    /// Устройство и inode; жёсткие ссылки на один файл имеют одинаковый идентификатор
+    #[serde(default)]
+    pub file_id: Option<FileId>,
+    /// Число жёстких ссылок на момент сканирования
+    #[serde(default = "default_links")]
+    pub links: u64,
     pub modified: u64,
     pub is_directory: bool,
 }
 
+fn default_links() -> u64 {
+    1
+}
+
+impl FileInfo {
+    pub(crate) fn from_metadata(path: &Path, metadata: &Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .unwrap_or(UNIX_EPOCH)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+
+        Self {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            allocated: allocated_size(metadata),
+            file_id: file_id(metadata),
+            links: link_count(metadata),
+            modified,
+            is_directory: false,
+        }
+    }
+}
+
+/// Идентификатор файла на диске: по нему жёсткие ссылки считаются один раз
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileId {
+    pub device: u64,
+    pub inode: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanResult {
     pub category: String,
     pub files_removed: usize,
+    /// Сколько из удалённых копий заменено жёсткими ссылками на оставленный файл
+    pub files_linked: usize,
+    /// Перемещено в корзину; в `files_removed` не входят
+    pub files_trashed: usize,
+    /// Место, которое освободится после очистки корзины
+    pub space_trashed: u64,
     pub space_freed: u64,
+    /// Место, которое занимают перенесённые в карантин файлы; освободится после очистки карантина
+    pub space_quarantined: u64,
+    pub dirs_pruned: usize,
     pub errors: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+    pub dry_run: bool,
+    pub would_remove: Vec<FileInfo>,
+    pub quarantine_session: Option<String>,
+    /// Предупреждения о том, что результат очистки может отличаться от ожидаемого
+    pub warnings: Vec<String>,
+}
+
+/// Отфильтрованные настройки категории, общие для всех потоков обхода
+struct CategoryFilters<'a> {
+    name: &'a str,
+    category: &'a CleanupCategory,
+    patterns: PatternSet,
+    exclusions: PatternSet,
+    min_size: u64,
+    max_size: u64,
+}
+
+/// Находка одного потока обхода
+enum Finding {
+    File(FileInfo),
+    SkippedBySize(u64),
+    Error(String),
+    /// Файл подошёл по шаблону; возраст и размер проверяются, когда собирается его каталог
+    Candidate(IndexedFile),
+    CutOff(CutOff),
+}
+
+/// Корень категории, который сейчас обходится
+struct ScanRoot<'a> {
+    path: &'a Path,
+    /// Реальный путь корня; задан только в безопасном режиме
+    resolved: Option<PathBuf>,
+    noatime: bool,
+    device: Option<u64>,
+}
+
+/// Индекс каталогов категории на время сканирования: отдаёт содержимое неизменившихся каталогов
+/// из прошлого индекса и собирает новый
+struct ScanCache<'a> {
+    service: &'a CleanerService,
+    filters: &'a CategoryFilters<'a>,
+    job: &'a Job,
+    previous: Option<&'a CategoryIndex>,
+    records: Mutex<HashMap<String, DirRecord>>,
+    /// Каталоги, изменённые позже этого момента, не запоминаются: на файловых системах с грубыми
+    /// отметками времени следующее изменение может не сдвинуть их время изменения
+    settled_before: SystemTime,
+}
+
+impl ScanCache<'_> {
+    fn classify(&self, candidate: &IndexedFile) -> Option<Finding> {
+        let finding = self.service.classify(candidate, self.filters)?;
+        if let Finding::File(file) = &finding {
+            self.job.file_matched(file.allocated);
+        }
+        Some(finding)
+    }
+}
+
+impl DirCache<Finding> for ScanCache<'_> {
+    fn lookup(&self, dir: &Path, metadata: &Metadata) -> Option<CachedDir<Finding>> {
+        let key = dir.to_str()?;
+        let record = self.previous?.dir(key)?;
+        if modified_parts(metadata)? != (record.modified_secs, record.modified_nanos) {
+            return None;
+        }
+
+        // Перезапись файла не меняет время изменения каталога, поэтому метаданные файлов перечитываются.
+        // Это дешевле чтения каталога; если файл пропал или сменил тип, каталог читается заново
+        let mut files = Vec::with_capacity(record.files.len());
+        for candidate in &record.files {
+            let path = Path::new(&candidate.file.path);
+            let metadata = std::fs::symlink_metadata(path).ok().filter(|metadata| metadata.is_file())?;
+            files.push(indexed_file(path, &metadata, self.filters.category.age_timestamp, false));
+        }
+
+        let mut found = Vec::with_capacity(files.len() + record.errors.len());
+        for candidate in &files {
+            self.job.file_seen(self.filters.name, dir);
+            found.extend(self.classify(candidate));
+        }
+        for error in &record.errors {
+            self.job.error_seen();
+            found.push(Finding::Error(error.clone()));
+        }
+        found.extend(record.cut_off.iter().cloned().map(Finding::CutOff));
+
+        self.records.lock().unwrap().insert(key.to_string(), DirRecord {
+            modified_secs: record.modified_secs,
+            modified_nanos: record.modified_nanos,
+            files,
+            errors: record.errors.clone(),
+            cut_off: record.cut_off.clone(),
+            subdirs: record.subdirs.clone(),
+        });
+        Some(CachedDir {
+            found,
+            subdirs: record.subdirs.iter().map(PathBuf::from).collect(),
+        })
+    }
+
+    fn store(&self, dir: &Path, metadata: &Metadata, found: Vec<Finding>, subdirs: Vec<PathBuf>) -> Vec<Finding> {
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        let mut cut_off = Vec::new();
+        let mut result = Vec::with_capacity(found.len());
+
+        for finding in found {
+            match finding {
+                Finding::Candidate(candidate) => {
+                    result.extend(self.classify(&candidate));
+                    files.push(candidate);
+                }
+                Finding::Error(error) => {
+                    errors.push(error.clone());
+                    result.push(Finding::Error(error));
+                }
+                Finding::CutOff(subtree) => {
+                    cut_off.push(subtree.clone());
+                    result.push(Finding::CutOff(subtree));
+                }
+                other => result.push(other),
+            }
+        }
+
+        // Каталоги с именами не в UTF-8 и недавно изменённые каталоги в следующий раз просто читаются заново
+        let settled = metadata.modified().is_ok_and(|modified| modified < self.settled_before);
+        let subdirs: Option<Vec<String>> = subdirs.iter().map(|subdir| subdir.to_str().map(str::to_string)).collect();
+
+        if let (true, Some(key), Some((modified_secs, modified_nanos)), Some(subdirs)) =
+            (settled, dir.to_str(), modified_parts(metadata), subdirs)
+        {
+            self.records.lock().unwrap().insert(key.to_string(), DirRecord {
+                modified_secs,
+                modified_nanos,
+                files,
+                errors,
+                cut_off,
+                subdirs,
+            });
+        }
+
+        result
+    }
+}
+
+/// Файл из результатов сканирования, который не был удалён при очистке
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
 }
 
 pub struct CleanerService {
     config: AppConfig,
-    scan_progress: f64,
-    clean_progress: f64,
+    protected: ProtectedPaths,
     scan_results: HashMap<String, ScanResult>,
 }
 
@@ -91,275 +369,1801 @@ impl CleanerService {
     pub fn new(config: AppConfig) -> Self {
         Self {
             config,
-            scan_progress: 0.0,
-            clean_progress: 0.0,
+            protected: ProtectedPaths::default(),
             scan_results: HashMap::new(),
         }
     }
 
-    pub async fn scan_system(&mut self) -> Result<serde_json::Value, CleanerError> {
-        self.scan_progress = 0.0;
-        self.scan_results.clear();
+    /// Копия сервиса для фоновой задачи: настройки и планы очистки выбранных категорий.
+    /// Задача работает с копией, а общий сервис тем временем доступен остальным командам
+    pub fn snapshot(&self, categories: &[String]) -> CleanerService {
+        Self {
+            config: self.config.clone(),
+            protected: self.protected.clone(),
+            scan_results: categories
+                .iter()
+                .filter_map(|name| self.scan_results.get_key_value(name))
+                .map(|(name, plan)| (name.clone(), plan.clone()))
+                .collect(),
+        }
+    }
 
-        let total_categories = self.config.cleanup_categories.len();
-        let mut processed = 0;
+    /// Забирает результаты сканирования, выполненного на копии из [`CleanerService::snapshot`]
+    pub fn store_scan(&mut self, snapshot: CleanerService) {
+        self.scan_results = snapshot.scan_results;
+    }
 
-        for (category_name, category) in &self.config.cleanup_categories {
-            if !category.enabled {
-                processed += 1;
-                self.scan_progress = processed as f64 / total_categories as f64;
-                continue;
+    /// Убирает из планов выбранных категорий всё, чего больше нет на диске
+    pub fn forget_removed(&mut self, categories: &[String]) {
+        for name in categories {
+            if let Some(plan) = self.scan_results.get_mut(name) {
+                plan.files.retain(|file| std::fs::symlink_metadata(&file.path).is_ok());
+                plan.recount();
             }
+        }
+    }
+
+    /// Сканирует все включённые категории. Выполняется в фоновой задаче `job`;
+    /// при отмене прежние результаты сканирования остаются нетронутыми.
+    /// Без `full_rescan` каталоги, не изменившиеся с прошлого сканирования, берутся из индекса
+    pub fn scan_system(&mut self, job: &Job, full_rescan: bool) -> Result<serde_json::Value, CleanerError> {
+        let pool = self.build_pool()?;
+        let mut index = ScanIndex::load();
+        let previous = if full_rescan { None } else { Some(&index) };
+
+        // Объём работы известен по прошлому сканированию, даже полному: прогресс идёт по каталогам, а не по категориям
+        job.expect_scan(
+            self.config.cleanup_categories
+                .iter()
+                .filter(|(_, category)| category.enabled)
+                .map(|(name, category)| {
+                    let estimate = ScanEstimate {
+                        expected_dirs: index.dir_count(name) as u64,
+                        roots: category.paths.len(),
+                    };
+                    (name.clone(), estimate)
+                })
+                .collect(),
+        );
+
+        // Категории сканируются параллельно, а внутри категории параллельно обходятся поддеревья
+        let scanned: Vec<(ScanResult, CategoryIndex)> = pool.install(|| {
+            self.config.cleanup_categories
+                .par_iter()
+                .filter(|(_, category)| category.enabled)
+                .map(|(category_name, category)| {
+                    let result = self.scan_category(category_name, category, job, previous);
+                    job.category_done(category_name);
+                    result
+                })
+                .collect::<Result<_, _>>()
+        })?;
+
+        if job.is_cancelled() {
+            return Err(CleanerError::Cancelled);
+        }
 
-            let result = self.scan_category(category_name, category).await?;
-            self.scan_results.insert(category_name.clone(), result);
-            
-            processed += 1;
-            self.scan_progress = processed as f64 / total_categories as f64;
+        let (mut results, indexes): (Vec<ScanResult>, HashMap<String, CategoryIndex>) = scanned
+            .into_iter()
+            .map(|(result, index)| {
+                let name = result.category.clone();
+                (result, (name, index))
+            })
+            .unzip();
+
+        // Индекс только ускоряет следующее сканирование: если его не удалось записать, оно будет полным
+        index.replace(indexes);
+        let _ = index.save();
+
+        // Каждый файл остаётся только в одной категории, иначе общий объём завышается
+        ownership::resolve_overlaps(&mut results, &self.config.cleanup_categories);
+        self.locate_mounts(&mut results);
+
+        self.scan_results.clear();
+        for result in results {
+            self.scan_results.insert(result.category.clone(), result);
         }
 
         Ok(serde_json::to_value(&self.scan_results)?)
     }
 
-    async fn scan_category(&self, category_name: &str, category: &CleanupCategory) -> Result<ScanResult, CleanerError> {
-        let mut total_files = 0;
-        let mut total_size = 0u64;
-        let mut files = Vec::new();
+    /// Отмечает у каждого результата раздел, где лежит бо́льшая часть найденного.
+    /// Если ничего не найдено — раздел первого существующего корня категории
+    fn locate_mounts(&self, results: &mut [ScanResult]) {
+        let mounts = MountTable::load();
 
-        for path in &category.paths {
-            if !path.exists() {
-                continue;
+        for result in results {
+            let mut shares: HashMap<&Path, u64> = HashMap::new();
+            for file in &result.files {
+                if let Some(mount) = mounts.find(Path::new(&file.path)) {
+                    *shares.entry(&mount.mount_point).or_default() += file.allocated;
+                }
             }
 
-            let walker = WalkDir::new(path)
-                .follow_links(false)
-                .max_depth(10);
+            let largest = shares
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(mount_point, _)| mount_point);
 
-            for entry in walker {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
+            let mount = largest.or_else(|| {
+                self.config.cleanup_categories
+                    .get(&result.category)
+                    .and_then(|category| category.paths.iter().find(|path| path.exists()))
+                    .and_then(|path| mounts.find(path))
+                    .map(|mount| mount.mount_point.as_path())
+            });
 
-                let file_path = entry.path();
-                
-                // Проверяем, что файл не в исключенных путях
-                if self.is_excluded(file_path) {
-                    continue;
-                }
+            result.mount = mount.map(|mount_point| mount_point.to_string_lossy().to_string());
+        }
+    }
 
-                // Проверяем паттерн файла
-                if !self.matches_pattern(file_path, &category.file_patterns) {
-                    continue;
-                }
+    /// Пул потоков для сканирования. `scan_threads = 0` означает «по числу ядер»
+    fn build_pool(&self) -> Result<ThreadPool, CleanerError> {
+        ThreadPoolBuilder::new()
+            .num_threads(self.config.scan_threads)
+            .build()
+            .map_err(|e| CleanerError::Unknown(e.to_string()))
+    }
 
-                // Проверяем возраст файла
-                if !self.is_old_enough(file_path, category.min_age_days) {
-                    continue;
-                }
+    /// Сканирует одну категорию. Вместе с результатом возвращает индекс её каталогов для следующего сканирования
+    fn scan_category(
+        &self,
+        category_name: &str,
+        category: &CleanupCategory,
+        job: &Job,
+        index: Option<&ScanIndex>,
+    ) -> Result<(ScanResult, CategoryIndex), CleanerError> {
+        let fingerprint = scan_index::fingerprint(&self.config, category);
+        let scanned_at = SystemTime::now();
+        let mut result = ScanResult {
+            category: category_name.to_string(),
+            total_files: 0,
+            total_size: 0,
+            files: Vec::new(),
+            errors: Vec::new(),
+            skipped_by_size: 0,
+            skipped_by_size_bytes: 0,
+            overlaps: Vec::new(),
+            cut_off: Vec::new(),
+            duplicates: Vec::new(),
+            mount: None,
+            scanned_at: Some(scanned_at),
+        };
 
-                let metadata = match entry.metadata() {
-                    Ok(m) => m,
-                    Err(_) => continue,
-                };
+        let (patterns, exclusions) = match self.compile_patterns(category) {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                job.error_seen();
+                result.errors.push(e.to_string());
+                return Ok((result, CategoryIndex::new(fingerprint, HashMap::new())));
+            }
+        };
 
-                if metadata.is_file() {
-                    let size = metadata.len();
-                    
-                    // Проверяем максимальный размер файла
-                    if size > self.config.max_file_size_mb * 1024 * 1024 {
-                        continue;
-                    }
+        let mounts = if category.age_timestamp == AgeTimestamp::Accessed {
+            MountTable::load()
+        } else {
+            MountTable::default()
+        };
 
-                    total_size += size;
-                    total_files += 1;
+        let filters = CategoryFilters {
+            name: category_name,
+            category,
+            patterns,
+            exclusions,
+            min_size: category.min_size.map_or(0, |size| size.0),
+            max_size: category
+                .max_size
+                .map_or(self.config.max_file_size_mb * 1024 * 1024, |size| size.0),
+        };
 
-                    let modified = metadata
-                        .modified()
-                        .unwrap_or(UNIX_EPOCH)
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_or(Duration::from_secs(0))
-                        .as_secs();
+        // Чтение файла меняет время доступа, но не время изменения каталога: по индексу
+        // такая категория пропустила бы файлы, которые давно не открывали
+        let index = index.filter(|_| category.age_timestamp != AgeTimestamp::Accessed);
 
-                    files.push(FileInfo {
-                        path: file_path.to_string_lossy().to_string(),
-                        size,
-                        modified,
-                        is_directory: false,
-                    });
+        let cache = ScanCache {
+            service: self,
+            filters: &filters,
+            job,
+            previous: index.and_then(|index| index.category(category_name, &fingerprint)),
+            records: Mutex::new(HashMap::new()),
+            settled_before: SystemTime::now() - Duration::from_secs(2),
+        };
+
+        let mut findings: Vec<Finding> = category.paths
+            .par_iter()
+            .flat_map_iter(|path| {
+                let found = self.scan_root(path, &filters, &mounts, &cache, job);
+                job.root_done(category_name);
+                found
+            })
+            .collect();
+
+        if category.trash_bin {
+            findings.extend(self.scan_trash(&filters, job));
+        }
+
+        for finding in findings {
+            // Кандидаты, не прошедшие через каталог (корень категории — сам файл), проверяются здесь
+            let finding = match finding {
+                Finding::Candidate(candidate) => match self.classify(&candidate, &filters) {
+                    Some(finding) => finding,
+                    None => continue,
+                },
+                finding => finding,
+            };
+
+            match finding {
+                Finding::File(file) => result.files.push(file),
+                Finding::SkippedBySize(size) => {
+                    result.skipped_by_size += 1;
+                    result.skipped_by_size_bytes += size;
                 }
+                Finding::Error(error) => result.errors.push(error),
+                Finding::CutOff(subtree) => result.cut_off.push(subtree),
+                Finding::Candidate(_) => {}
             }
         }
 
-        Ok(ScanResult {
-            category: category_name.to_string(),
-            total_files,
-            total_size,
-            files,
-        })
+        // Найденные файлы — только кандидаты в дубликаты, удалять можно лишние копии из наборов
+        if let Some(settings) = &category.duplicates {
+            let (sets, errors) = duplicates::find_duplicates(std::mem::take(&mut result.files), settings, &category.paths, job);
+            result.files = sets.iter().flat_map(|set| set.removable().cloned()).collect();
+            result.errors.extend(errors);
+            result.duplicates = sets;
+        }
+
+        // Порядок обхода зависит от планировщика потоков, поэтому выдаём результат в стабильном порядке
+        result.files.sort_by(|a, b| a.path.cmp(&b.path));
+        result.errors.sort();
+        result.cut_off.sort_by(|a, b| a.path.cmp(&b.path));
+        result.recount();
+
+        let records = cache.records.into_inner().unwrap();
+        Ok((result, CategoryIndex::new(fingerprint, records)))
     }
 
-    pub async fn clean_categories(&mut self, categories: Vec<String>) -> Result<serde_json::Value, CleanerError> {
-        self.clean_progress = 0.0;
-        let mut results = Vec::new();
-        let total_categories = categories.len();
-        let mut processed = 0;
+    /// Шаблоны категории и исключения: глобальные шаблоны и шаблоны категории с той же семантикой, что и file_patterns
+    fn compile_patterns(&self, category: &CleanupCategory) -> Result<(PatternSet, PatternSet), CleanerError> {
+        let exclude_patterns: Vec<String> = self.config.excluded_patterns
+            .iter()
+            .chain(&category.exclude)
+            .cloned()
+            .collect();
 
-        for category_name in categories {
-            if let Some(category) = self.config.cleanup_categories.get(&category_name) {
-                if !category.enabled {
-                    continue;
-                }
+        let patterns = PatternSet::new(&category.file_patterns, category.case_insensitive)?;
+        let exclusions = PatternSet::exclusions(&exclude_patterns, category.case_insensitive)?;
+        Ok((patterns, exclusions))
+    }
 
-                let result = self.clean_category(&category_name, category).await?;
-                results.push(result);
-            }
-            
-            processed += 1;
-            self.clean_progress = processed as f64 / total_categories as f64;
+    fn scan_root(
+        &self,
+        path: &Path,
+        filters: &CategoryFilters,
+        mounts: &MountTable,
+        cache: &ScanCache,
+        job: &Job,
+    ) -> Vec<Finding> {
+        if !path.exists() {
+            return Vec::new();
         }
 
-        Ok(serde_json::to_value(&results)?)
-    }
+        // В безопасном режиме корень категории сверяется со списком защищённых путей
+        let resolved = if self.config.safe_mode {
+            match self.protected.check_category_root(path) {
+                Ok(resolved) => Some(resolved),
+                Err(e) => {
+                    job.error_seen();
+                    return vec![Finding::Error(e.to_string())];
+                }
+            }
+        } else {
+            None
+        };
 
-    async fn clean_category(&self, category_name: &str, category: &CleanupCategory) -> Result<CleanResult, CleanerError> {
-        let mut files_removed = 0;
-        let mut space_freed = 0u64;
-        let mut error_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        // На разделах с noatime время доступа не обновляется, поэтому ему нельзя доверять
+        let noatime = filters.category.age_timestamp == AgeTimestamp::Accessed
+            && mounts.find(path).is_some_and(|mount| mount.has_option("noatime"));
 
-        for path in &category.paths {
-            if !path.exists() {
-                continue;
+        let root = ScanRoot {
+            path,
+            resolved,
+            noatime,
+            device: std::fs::metadata(path).ok().and_then(|metadata| file_id(&metadata)).map(|id| id.device),
+        };
+
+        let visitor = |entry: &WalkEntry, found: &mut Vec<Finding>| {
+            // После отмены обход сворачивается: в новые каталоги больше не спускаемся
+            if job.is_cancelled() {
+                return Visit::SkipDir;
             }
 
-            let walker = WalkDir::new(path)
-                .follow_links(false)
-                .max_depth(10);
+            if entry.metadata.is_dir() {
+                job.dir_seen(filters.name, &entry.path);
+            } else {
+                job.file_seen(filters.name, entry.path.parent().unwrap_or(path));
+            }
 
-            for entry in walker {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
+            let before = found.len();
+            let visit = self.visit_entry(&root, filters, entry, found);
 
-                let file_path = entry.path();
-                
-                if self.is_excluded(file_path) {
-                    continue;
+            for finding in &found[before..] {
+                match finding {
+                    Finding::File(file) => job.file_matched(file.allocated),
+                    Finding::Error(_) => job.error_seen(),
+                    Finding::SkippedBySize(_) | Finding::Candidate(_) | Finding::CutOff(_) => {}
                 }
+            }
+            visit
+        };
+
+        // Нечитаемый каталог обход пропускает, но в отчёт он попадает
+        let unreadable = |dir: &Path, error: &io::Error, found: &mut Vec<Finding>| {
+            job.error_seen();
+            found.push(Finding::Error(format!("{}: {}", dir.display(), translate_error(error))));
+        };
+
+        // Глубину ограничивает посетитель, чтобы отрезанные поддеревья попали в отчёт
+        let follow_links = filters.category.symlinks == SymlinkPolicy::Follow;
+        walker::walk_cached(path, usize::MAX, follow_links, visitor, unreadable, cache)
+    }
+
+    /// Элементы корзин freedesktop.org. Каждая запись `files/` берётся целиком, как каталог-единица;
+    /// шаблоны категории проверяются по её имени, возраст — по дате удаления в корзину
+    fn scan_trash(&self, filters: &CategoryFilters, job: &Job) -> Vec<Finding> {
+        let mut found = Vec::new();
+
+        for trash in trash::trash_dirs(&MountTable::load()) {
+            if job.is_cancelled() {
+                break;
+            }
 
-                if !self.matches_pattern(file_path, &category.file_patterns) {
+            let files_dir = trash.files_dir();
+            job.dir_seen(filters.name, &files_dir);
+            let entries = match trash::entries(&trash) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    job.error_seen();
+                    found.push(Finding::Error(format!("{}: {}", trash.root.display(), translate_error(&e))));
                     continue;
                 }
+            };
+            let sizes = trash::DirectorySizes::load(&trash.root);
 
-                if !self.is_old_enough(file_path, category.min_age_days) {
+            for entry in entries {
+                job.file_seen(filters.name, &files_dir);
+                let name = Path::new(&entry.name);
+                if self.is_excluded(&entry.path, name, &filters.exclusions) || !filters.patterns.is_match(name) {
                     continue;
                 }
 
-                let metadata = match entry.metadata() {
-                    Ok(m) => m,
+                let metadata = match std::fs::symlink_metadata(&entry.path) {
+                    Ok(metadata) => metadata,
                     Err(_) => continue,
                 };
 
-                if metadata.is_file() {
-                    let size = metadata.len();
-                    
-                    if size > self.config.max_file_size_mb * 1024 * 1024 {
-                        continue;
-                    }
+                // Элемент без описания не восстановить, его возраст — по времени изменения
+                let deleted_at = entry.deleted_at.unwrap_or_else(|| metadata.modified().unwrap_or(UNIX_EPOCH));
+                if !self.is_within_age(deleted_at, filters.category) {
+                    continue;
+                }
 
-                    match std::fs::remove_file(file_path) {
-                        Ok(_) => {
-                            files_removed += 1;
-                            space_freed += size;
-                        }
-                        Err(e) => {
-                            let translated_error = translate_error(&e);
-                            *error_counts.entry(translated_error).or_insert(0) += 1;
+                let file = if metadata.is_dir() {
+                    // По кешу известен только размер на диске; дата изменения — у описания
+                    let summary = match sizes.size_of(&entry) {
+                        Some(size) => Some((size, size, entry.info_modified.unwrap_or(0))),
+                        None => summarize_dir(&entry.path, AgeTimestamp::Modified, false, |_| true)
+                            .map(|summary| (summary.size, summary.allocated, summary.modified)),
+                    };
+                    let (size, allocated, modified) = match summary {
+                        Some(summary) => summary,
+                        None => {
+                            job.error_seen();
+                            found.push(Finding::Error(format!("{}: не удалось прочитать содержимое", entry.path.display())));
+                            continue;
                         }
+                    };
+                    FileInfo {
+                        path: entry.path.to_string_lossy().to_string(),
+                        size,
+                        allocated,
+                        file_id: None,
+                        links: 1,
+                        modified,
+                        is_directory: true,
                     }
+                } else {
+                    FileInfo::from_metadata(&entry.path, &metadata)
+                };
+
+                if file.size < filters.min_size || file.size > filters.max_size {
+                    found.push(Finding::SkippedBySize(file.size));
+                    continue;
                 }
+                job.file_matched(file.allocated);
+                found.push(Finding::File(file));
             }
         }
 
-        // Преобразуем подсчеты ошибок в читаемый формат
-        let mut errors = Vec::new();
-        for (error_type, count) in error_counts {
-            if count == 1 {
-                errors.push(error_type);
-            } else {
-                errors.push(format!("{} ({} файлов)", error_type, count));
+        found
+    }
+
+    /// Применяет фильтры категории к одному элементу дерева
+    fn visit_entry(
+        &self,
+        root: &ScanRoot,
+        filters: &CategoryFilters,
+        entry: &WalkEntry,
+        found: &mut Vec<Finding>,
+    ) -> Visit {
+        let category = filters.category;
+        let file_path = entry.path.as_path();
+        let relative = file_path.strip_prefix(root.path).unwrap_or(file_path);
+        let is_dir = entry.metadata.is_dir();
+
+        // Защищённые деревья внутри корня пропускаем целиком
+        if let Some(resolved_root) = &root.resolved {
+            if let Err(e) = self.protected.check_entry(&resolved_root.join(relative)) {
+                found.push(Finding::Error(e.to_string()));
+                return Visit::SkipDir;
+            }
+
+            // Раскрытая ссылка может вести куда угодно, поэтому проверяем и её цель
+            if entry.is_symlink && is_dir {
+                let target = file_path.canonicalize().map_err(CleanerError::from);
+                if let Err(e) = target.and_then(|target| self.protected.check_entry(&target)) {
+                    found.push(Finding::Error(e.to_string()));
+                    return Visit::SkipDir;
+                }
             }
         }
 
-        Ok(CleanResult {
-            category: category_name.to_string(),
-            files_removed,
-            space_freed,
-            errors,
-        })
-    }
+        // Исключения важнее всех остальных правил, исключённые каталоги пропускаем целиком
+        if self.is_excluded(file_path, relative, &filters.exclusions) {
+            return Visit::SkipDir;
+        }
 
-    fn is_excluded(&self, path: &Path) -> bool {
-        self.config.excluded_paths.iter().any(|excluded| {
-            path.starts_with(excluded)
-        })
-    }
+        // В общих каталогах чужие файлы не трогаем, а в чужие каталоги не спускаемся
+        if category.owned_by_user && entry.depth > 0 && !is_owned(&entry.metadata) {
+            return Visit::SkipDir;
+        }
 
-    fn matches_pattern(&self, path: &Path, patterns: &[String]) -> bool {
-        if patterns.is_empty() {
-            return true;
+        if is_dir {
+            // Каталоги, подходящие под шаблоны вида `name/`, удаляются целиком
+            let is_unit = entry.depth > 0 && !entry.is_symlink && !entry.is_loop && filters.patterns.is_dir_match(relative);
+            if is_unit && self.take_dir_unit(root, filters, entry, found) {
+                return Visit::SkipDir;
+            }
+            return self.descend(root, category, entry, found);
         }
 
-        patterns.iter().any(|pattern| {
-            if let Some(file_name) = path.file_name() {
-                let file_name = file_name.to_string_lossy();
-                self.matches_glob(&file_name, pattern)
-            } else {
-                false
+        // Ссылки на файлы не раскрываются никогда: удаление ссылки не освобождает место её цели
+        if entry.is_symlink {
+            let is_dir_link = std::fs::metadata(file_path).is_ok_and(|metadata| metadata.is_dir());
+            if is_dir_link && category.symlinks == SymlinkPolicy::Skip {
+                found.push(Finding::CutOff(CutOff {
+                    path: file_path.to_string_lossy().to_string(),
+                    reason: CutOffReason::Symlink,
+                }));
             }
-        })
+            return Visit::Continue;
+        }
+
+        // Проверяем паттерн файла относительно корня категории
+        if !entry.metadata.is_file() || !filters.patterns.is_match(relative) {
+            return Visit::Continue;
+        }
+
+        // Возраст и размер проверяются позже: кандидат попадает в индекс, и со временем он может подойти по возрасту
+        found.push(Finding::Candidate(indexed_file(file_path, &entry.metadata, category.age_timestamp, root.noatime)));
+
+        Visit::Continue
     }
 
-    fn matches_glob(&self, text: &str, pattern: &str) -> bool {
-        // Простая реализация glob matching
-        if pattern == "*" {
-            return true;
+    /// Пытается взять каталог целиком. Возвращает `false`, если его содержимое нужно обойти по отдельности
+    fn take_dir_unit(&self, root: &ScanRoot, filters: &CategoryFilters, entry: &WalkEntry, found: &mut Vec<Finding>) -> bool {
+        let category = filters.category;
+        let dir = entry.path.as_path();
+
+        // Каталог нельзя удалить целиком, если внутри есть исключённые или защищённые пути
+        let summary = summarize_dir(dir, category.age_timestamp, root.noatime, |inner| {
+            let inner_relative = inner.strip_prefix(root.path).unwrap_or(inner);
+            let protected = root.resolved.as_ref().is_some_and(|resolved_root| {
+                self.protected.check_entry(&resolved_root.join(inner_relative)).is_err()
+            });
+            !protected && !self.is_excluded(inner, inner_relative, &filters.exclusions)
+        });
+
+        let summary = match summary {
+            Some(summary) => summary,
+            None => return false,
+        };
+
+        // Слишком свежий каталог не берём целиком, но файлы в нём ещё могут подойти по отдельности
+        if !self.is_within_age(summary.newest, category) {
+            return false;
         }
-        
-        if pattern.starts_with("*.") {
-            let ext = &pattern[2..];
-            return text.ends_with(ext);
+
+        if summary.size < filters.min_size || summary.size > filters.max_size {
+            found.push(Finding::SkippedBySize(summary.size));
+        } else {
+            found.push(Finding::File(FileInfo {
+                path: dir.to_string_lossy().to_string(),
+                size: summary.size,
+                allocated: summary.allocated,
+                file_id: None,
+                links: 1,
+                modified: summary.modified,
+                is_directory: true,
+            }));
         }
-        
-        text == pattern
+        true
     }
 
-    fn is_old_enough(&self, path: &Path, min_age_days: u32) -> bool {
-        if min_age_days == 0 {
-            return true;
+    /// Решает, спускаться ли в каталог, по настройкам обхода категории. Отрезанные поддеревья попадают в отчёт
+    fn descend(&self, root: &ScanRoot, category: &CleanupCategory, entry: &WalkEntry, found: &mut Vec<Finding>) -> Visit {
+        let device = file_id(&entry.metadata).map(|id| id.device);
+
+        // Петли распознаёт обход по устройству и inode; где их нет, остаётся проверка пути ссылки
+        let is_loop = entry.is_loop
+            || (entry.is_symlink && file_id(&entry.metadata).is_none() && is_symlink_loop(&entry.path));
+
+        let reason = if is_loop {
+            CutOffReason::SymlinkLoop
+        } else if category.one_file_system && root.device.is_some() && device != root.device {
+            CutOffReason::OtherFilesystem
+        } else if category.max_depth.is_some_and(|max_depth| entry.depth >= max_depth) {
+            CutOffReason::MaxDepth
+        } else {
+            return Visit::Continue;
+        };
+
+        found.push(Finding::CutOff(CutOff {
+            path: entry.path.to_string_lossy().to_string(),
+            reason,
+        }));
+        Visit::SkipDir
+    }
+
+    /// Проверяет возраст и границы размера файла, подошедшего по шаблону
+    fn classify(&self, candidate: &IndexedFile, filters: &CategoryFilters) -> Option<Finding> {
+        let timestamp = UNIX_EPOCH + Duration::from_secs(candidate.timestamp);
+        if !self.is_within_age(timestamp, filters.category) {
+            return None;
         }
 
-        let metadata = match std::fs::metadata(path) {
-            Ok(m) => m,
-            Err(_) => return false,
+        // Границы размера: собственные у категории или глобальный максимум
+        let size = candidate.file.size;
+        if size < filters.min_size || size > filters.max_size {
+            return Some(Finding::SkippedBySize(size));
+        }
+
+        Some(Finding::File(candidate.file.clone()))
+    }
+
+    /// Очищает выбранные категории в фоновой задаче `job`. При отмене возвращает то, что успело выполниться
+    pub fn clean_categories(&mut self, categories: Vec<String>, dry_run: bool, job: &Job) -> Result<serde_json::Value, CleanerError> {
+        let total_categories = categories.len();
+
+        // При включённом резервировании файлы переносятся в карантин, а не удаляются
+        let mut session = if self.config.backup_enabled && !dry_run {
+            Some(QuarantineSession::create()?)
+        } else {
+            None
         };
 
-        let modified = metadata
-            .modified()
-            .unwrap_or(UNIX_EPOCH)
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or(Duration::from_secs(0));
+        // Пробный прогон заново применяет фильтры текущей конфигурации и ничего не трогает на диске.
+        // Настоящая очистка удаляет только то, что пользователь видел в результатах последнего сканирования
+        let dry_run_plans = if dry_run {
+            Some(self.scan_selected(&categories, job)?)
+        } else {
+            None
+        };
+
+        let plans = dry_run_plans.as_ref().unwrap_or(&self.scan_results);
+
+        // Оставляемую копию дубликата не должна удалить другая категория той же очистки,
+        // а место жёстких ссылок из разных категорий считается по всем ним сразу
+        let planned: PlannedRemovals = categories
+            .iter()
+            .filter_map(|name| plans.get(name))
+            .flat_map(|plan| &plan.files)
+            .collect();
+
+        let mut results = Vec::new();
+        for (stage, category_name) in categories.into_iter().enumerate() {
+            job.begin_stage(stage, total_categories);
+
+            let plan = match &dry_run_plans {
+                Some(plans) => plans.get(&category_name),
+                None => self.scan_results.get(&category_name),
+            };
+
+            let result = match plan {
+                Some(plan) => Some(self.clean_category(&category_name, plan, &planned, dry_run, session.as_mut(), job)?),
+                None => None,
+            };
+
+            match result {
+                Some(result) => {
+                    if !dry_run {
+                        self.forget_removed(std::slice::from_ref(&category_name));
+                    }
+                    results.push(result);
+                }
+                None => {
+                    let error = if dry_run {
+                        "Категория не найдена в конфигурации"
+                    } else {
+                        "Категория не была просканирована"
+                    };
+                    results.push(CleanResult {
+                        category: category_name.clone(),
+                        files_removed: 0,
+                        files_linked: 0,
+                        files_trashed: 0,
+                        space_trashed: 0,
+                        space_freed: 0,
+                        space_quarantined: 0,
+                        dirs_pruned: 0,
+                        errors: vec![error.to_string()],
+                        skipped: Vec::new(),
+                        dry_run,
+                        would_remove: Vec::new(),
+                        quarantine_session: None,
+                        warnings: Vec::new(),
+                    });
+                }
+            }
+
+            if let Some(session) = &session {
+                session.save()?;
+            }
+
+            job.stage_progress(1.0);
+
+            if job.is_cancelled() {
+                break;
+            }
+        }
+
+        if let Some(session) = session {
+            session.finish()?;
+        }
 
-        let min_age = Duration::from_secs(min_age_days as u64 * 24 * 60 * 60);
-        modified >= min_age
+        Ok(serde_json::to_value(&results)?)
     }
 
-    pub fn get_scan_progress(&self) -> f64 {
-        self.scan_progress
+    /// Итоги последнего сканирования по выбранным категориям (по всем, если `categories` не задан)
+    pub fn scan_totals(&self, categories: Option<&[String]>) -> Result<serde_json::Value, CleanerError> {
+        let plans: Vec<&ScanResult> = match categories {
+            Some(categories) => categories.iter().filter_map(|name| self.scan_results.get(name)).collect(),
+            None => self.scan_results.values().collect(),
+        };
+
+        let planned: PlannedRemovals = plans.iter().flat_map(|plan| &plan.files).collect();
+        let totals = ScanTotals {
+            total_files: plans.iter().map(|plan| plan.files.len()).sum(),
+            total_size: plans
+                .iter()
+                .flat_map(|plan| &plan.files)
+                .map(|file| planned.reclaimed(file))
+                .sum(),
+        };
+        Ok(serde_json::to_value(totals)?)
     }
 
-    pub fn get_clean_progress(&self) -> f64 {
-        self.clean_progress
+    /// Сканирует выбранные категории для пробного прогона. Пересечения разрешаются только между ними
+    fn scan_selected(&self, categories: &[String], job: &Job) -> Result<HashMap<String, ScanResult>, CleanerError> {
+        let mut plans: Vec<ScanResult> = self.build_pool()?.install(|| {
+            categories
+                .par_iter()
+                .filter_map(|name| self.config.cleanup_categories.get(name).map(|category| (name, category)))
+                .map(|(name, category)| self.scan_category(name, category, job, None).map(|(plan, _)| plan))
+                .collect::<Result<_, _>>()
+        })?;
+
+        ownership::resolve_overlaps(&mut plans, &self.config.cleanup_categories);
+        self.locate_mounts(&mut plans);
+        Ok(plans.into_iter().map(|plan| (plan.category.clone(), plan)).collect())
+    }
+
+    fn clean_category(
+        &self,
+        category_name: &str,
+        plan: &ScanResult,
+        planned: &PlannedRemovals,
+        dry_run: bool,
+        mut quarantine: Option<&mut QuarantineSession>,
+        job: &Job,
+    ) -> Result<CleanResult, CleanerError> {
+        let category = self.config.cleanup_categories.get(category_name);
+        let settings = category.and_then(|category| category.duplicates.as_ref());
+        let hardlink = settings.is_some_and(|settings| settings.hardlink);
+        let shred = category.and_then(|category| category.shred.as_ref());
+        let mut warnings = Vec::new();
+
+        let trash_bin = category.is_some_and(|category| category.trash_bin);
+        let trash_mounts = match category {
+            Some(category) if category.trash && !trash_bin && shred.is_none() => Some(MountTable::load()),
+            _ => None,
+        };
+
+        // Копия в карантине сделала бы затирание бессмысленным
+        if shred.is_some() {
+            if quarantine.is_some() {
+                warnings.push("Резервная копия не создаётся: файлы категории затираются".to_string());
+            }
+            if category.is_some_and(|category| category.trash) {
+                warnings.push("Корзина не используется: файлы категории затираются".to_string());
+            }
+            quarantine = None;
+            warnings.extend(self.shred_warnings(plan));
+        }
+
+        // Из корзины файлы и так можно вернуть, карантин не нужен
+        if trash_mounts.is_some() {
+            quarantine = None;
+        }
+
+        // Пробный прогон идёт без сессии карантина, но место должен считать так же, как настоящая очистка
+        let quarantined = quarantine.is_some()
+            || (dry_run && self.config.backup_enabled && shred.is_none() && trash_mounts.is_none());
+
+        // Лишняя копия дубликата → оставляемый файл из того же набора
+        let originals: HashMap<&str, &FileInfo> = plan.duplicates
+            .iter()
+            .filter_map(|set| set.kept().map(|kept| (set, kept)))
+            .flat_map(|(set, kept)| set.removable().map(move |file| (file.path.as_str(), kept)))
+            .collect();
+
+        let mut files_removed = 0;
+        let mut files_linked = 0;
+        let mut files_trashed = 0;
+        let mut space_freed = 0u64;
+        let mut space_trashed = 0u64;
+        let mut space_quarantined = 0u64;
+        let mut error_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        let mut skipped = Vec::new();
+        let mut would_remove = Vec::new();
+        let mut touched_dirs = Vec::new();
+        let mut size_caches = trash::SizeCaches::default();
+
+        for (index, file) in plan.files.iter().enumerate() {
+            // Отмена срабатывает между файлами, текущий файл всегда обрабатывается до конца
+            if job.is_cancelled() {
+                break;
+            }
+
+            let file_path = Path::new(&file.path);
+
+            // При пробном прогоне файлы уже посчитаны сканированием
+            if !dry_run {
+                job.stage_progress(index as f64 / plan.files.len() as f64);
+                job.file_seen(category_name, file_path.parent().unwrap_or(file_path));
+            }
+
+            // Перед удалением убеждаемся, что файл не изменился с момента сканирования.
+            // Каталог в корзине, размер которого взят из кеша, сверяется с тем же кешем
+            let cached = trash_bin && file.is_directory && size_caches.cached_size(file_path) == Some(file.allocated);
+            if let Some(reason) = self.verify_unchanged(file_path, file).filter(|_| !cached) {
+                skipped.push(SkippedFile {
+                    path: file.path.clone(),
+                    reason,
+                });
+                continue;
+            }
+
+            if self.config.safe_mode {
+                if let Err(e) = self.protected.check_removal(file_path) {
+                    skipped.push(SkippedFile {
+                        path: file.path.clone(),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            }
+
+            let original = originals.get(file.path.as_str()).copied();
+            if settings.is_some() {
+                if let Some(reason) = self.check_original(file, original, planned, hardlink) {
+                    skipped.push(SkippedFile {
+                        path: file.path.clone(),
+                        reason,
+                    });
+                    continue;
+                }
+            }
+            let link_to = original.filter(|_| hardlink).map(|original| Path::new(&original.path));
+
+            // Затирание через одну ссылку испортило бы содержимое, видимое по остальным
+            if shred.is_some() && link_to.is_none() {
+                if let Err(e) = shred::check_links(file_path) {
+                    skipped.push(SkippedFile {
+                        path: file.path.clone(),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            }
+
+            // Копия, заменяемая ссылкой, в корзину не попадает: её содержимое остаётся у оригинала
+            let trash_into = trash_mounts.as_ref().filter(|_| link_to.is_none());
+
+            if dry_run {
+                if link_to.is_some() {
+                    files_linked += 1;
+                }
+
+                // Место освободится, только если план забирает все жёсткие ссылки на файл
+                let reclaimed = planned.reclaimed(file);
+                if trash_into.is_some() {
+                    files_trashed += 1;
+                    space_trashed += reclaimed;
+                } else if quarantined {
+                    files_removed += 1;
+                    space_quarantined += reclaimed;
+                } else {
+                    files_removed += 1;
+                    space_freed += reclaimed;
+                }
+                would_remove.push(file.clone());
+                continue;
+            }
+
+            // Пока у файла остаются другие жёсткие ссылки, удаление этой места не освобождает
+            let reclaimed = if file.is_directory {
+                file.allocated
+            } else {
+                let links = std::fs::symlink_metadata(file_path).map_or(file.links, |metadata| link_count(&metadata));
+                if links <= 1 { file.allocated } else { 0 }
+            };
+
+            let removed = if let Some(mounts) = trash_into {
+                trash::move_to_trash(file_path, mounts).map(|trashed| {
+                    // Кеш размеров необязателен: без записи размер каталога просто посчитают заново
+                    if file.is_directory {
+                        let _ = size_caches.record_dir_size(&trashed, file.allocated);
+                    }
+                })
+            } else {
+                match (quarantine.as_deref_mut(), file.is_directory, link_to) {
+                    (Some(session), false, Some(original)) => session
+                        .store(category_name, file)
+                        .and_then(|_| std::fs::hard_link(original, file_path)),
+                    (Some(session), false, None) => session.store(category_name, file),
+                    (Some(session), true, _) => session.store_directory(category_name, file_path),
+                    (None, false, Some(original)) => duplicates::replace_with_link(original, file_path),
+                    (None, false, None) => match shred {
+                        Some(shred) => shred::shred_file(file_path, shred.passes),
+                        None => std::fs::remove_file(file_path),
+                    },
+                    (None, true, _) => match shred {
+                        Some(shred) => shred::shred_dir(file_path, shred.passes),
+                        None => std::fs::remove_dir_all(file_path),
+                    },
+                }
+            };
+
+            // Элемент корзины удаляется вместе с описанием, иначе в корзине останется запись без файла
+            let removed = match removed {
+                Ok(()) if trash_bin && trash::is_entry(file_path) => size_caches.forget(file_path),
+                removed => removed,
+            };
+
+            match removed {
+                Ok(_) => {
+                    job.file_matched(reclaimed);
+                    if trash_into.is_some() {
+                        files_trashed += 1;
+                        space_trashed += reclaimed;
+                    } else if quarantined {
+                        files_removed += 1;
+                        space_quarantined += reclaimed;
+                    } else {
+                        files_removed += 1;
+                        space_freed += reclaimed;
+                    }
+                    if link_to.is_some() {
+                        files_linked += 1;
+                    }
+                    if let Some(parent) = file_path.parent() {
+                        touched_dirs.push(parent.to_path_buf());
+                    }
+                }
+                Err(e) => {
+                    job.error_seen();
+                    let translated_error = translate_error(&e);
+                    *error_counts.entry(translated_error).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Кеш размеров необязателен: без записи размер каталога просто посчитают заново
+        let _ = size_caches.save();
+
+        // Новые файлы рядом с удалёнными пользователь не видел: они остаются на месте и попадают в отчёт
+        if !dry_run && !job.is_cancelled() {
+            if let Some(category) = category {
+                skipped.extend(self.appeared_files(category, plan));
+            }
+        }
+
+        // Описания без файлов остаются от чужих прерванных удалений; восстановить по ним нечего
+        if trash_bin && !dry_run && !job.is_cancelled() {
+            for trash in trash::trash_dirs(&MountTable::load()) {
+                if let Err(e) = trash::remove_orphaned_info(&trash) {
+                    job.error_seen();
+                    *error_counts.entry(translate_error(&e)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Убираем оставшийся от кеша «скелет» пустых каталогов
+        let dirs_pruned = match self.config.cleanup_categories.get(category_name) {
+            Some(category) if category.prune_empty_dirs && !dry_run => {
+                self.prune_empty_dirs(&category.paths, touched_dirs)
+            }
+            _ => 0,
+        };
+
+        // Преобразуем подсчеты ошибок в читаемый формат
+        let mut errors = Vec::new();
+        for (error_type, count) in error_counts {
+            if count == 1 {
+                errors.push(error_type);
+            } else {
+                errors.push(format!("{} ({} файлов)", error_type, count));
+            }
+        }
+
+        Ok(CleanResult {
+            category: category_name.to_string(),
+            files_removed,
+            files_linked,
+            files_trashed,
+            space_freed,
+            space_trashed,
+            space_quarantined,
+            dirs_pruned,
+            errors,
+            skipped,
+            dry_run,
+            would_remove,
+            quarantine_session: quarantine.map(|session| session.id().to_string()),
+            warnings,
+        })
+    }
+
+    /// Разделы, на которых затирание не гарантирует, что удалённое нельзя восстановить.
+    /// Каталог затирается вместе со всем, что смонтировано внутри него
+    fn shred_warnings(&self, plan: &ScanResult) -> Vec<String> {
+        let mounts = MountTable::load();
+        let mut checked = HashSet::new();
+
+        plan.files
+            .iter()
+            .flat_map(|file| {
+                let path = Path::new(&file.path);
+                let nested = file.is_directory.then(|| mounts.nested(path)).into_iter().flatten();
+                mounts.find(path).into_iter().chain(nested)
+            })
+            .filter(|mount| checked.insert(mount.mount_point.clone()))
+            .filter_map(shred::untrusted_reason)
+            .collect()
+    }
+
+    /// Файлы категории, появившиеся после сканирования в каталогах, где лежат файлы из плана
+    fn appeared_files(&self, category: &CleanupCategory, plan: &ScanResult) -> Vec<SkippedFile> {
+        let (scanned_at, (patterns, exclusions)) = match (plan.scanned_at, self.compile_patterns(category)) {
+            (Some(scanned_at), Ok(compiled)) => (scanned_at, compiled),
+            _ => return Vec::new(),
+        };
+
+        let planned: HashSet<&str> = plan.files.iter().map(|file| file.path.as_str()).collect();
+        let dirs: BTreeSet<&Path> = plan.files.iter().filter_map(|file| Path::new(&file.path).parent()).collect();
+        let mut appeared = Vec::new();
+
+        for dir in dirs {
+            let root = match category.paths.iter().find(|root| dir.starts_with(root)) {
+                Some(root) => root,
+                None => continue,
+            };
+            let entries = match std::fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let metadata = match std::fs::symlink_metadata(&path) {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                let matches = if metadata.is_dir() {
+                    patterns.is_dir_match(relative)
+                } else {
+                    metadata.is_file() && patterns.is_match(relative)
+                };
+                let path_text = path.to_string_lossy();
+                if !matches || planned.contains(path_text.as_ref()) || self.is_excluded(&path, relative, &exclusions) {
+                    continue;
+                }
+
+                // Время изменения inode сдвигается и при создании, и при переносе файла в каталог.
+                // Ядро берёт его из грубых часов, поэтому файл, созданный сразу после начала
+                // сканирования, может получить отметку чуть раньше; запас тот же, что у индекса
+                let appeared_at = changed_time(&metadata)
+                    .or_else(|| metadata.created().ok())
+                    .unwrap_or(UNIX_EPOCH);
+                if appeared_at + Duration::from_secs(2) >= scanned_at {
+                    appeared.push(SkippedFile {
+                        path: path_text.to_string(),
+                        reason: "Файл появился после сканирования".to_string(),
+                    });
+                }
+            }
+        }
+
+        appeared
+    }
+
+    /// Лишнюю копию дубликата можно убрать, только пока оставляемый файл на месте и не изменился.
+    /// Возвращает причину пропуска
+    fn check_original(
+        &self,
+        file: &FileInfo,
+        original: Option<&FileInfo>,
+        planned: &PlannedRemovals,
+        hardlink: bool,
+    ) -> Option<String> {
+        let original = match original {
+            Some(original) => original,
+            None => return Some("Не найдена оставляемая копия".to_string()),
+        };
+
+        if planned.contains(&original.path) {
+            return Some("Оставляемую копию удаляет другая категория".to_string());
+        }
+
+        let device = |file: &FileInfo| file.file_id.map(|id| id.device);
+        if hardlink && device(original) != device(file) {
+            return Some("Копии на разных файловых системах, жёсткую ссылку создать нельзя".to_string());
+        }
+
+        self.verify_unchanged(Path::new(&original.path), original)
+            .map(|reason| format!("Оставляемая копия: {}", reason.to_lowercase()))
+    }
+
+    /// Сверяет файл на диске с записью из сканирования. Возвращает причину пропуска, если они расходятся
+    fn verify_unchanged(&self, path: &Path, file: &FileInfo) -> Option<String> {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(m) => m,
+            Err(_) => return Some("Файл исчез после сканирования".to_string()),
+        };
+
+        if metadata.is_dir() != file.is_directory || metadata.file_type().is_symlink() {
+            return Some("Тип файла изменился после сканирования".to_string());
+        }
+
+        // Каталог сверяем по суммарному размеру и самой свежей дате изменения внутри
+        if file.is_directory {
+            return match summarize_dir(path, AgeTimestamp::Modified, false, |_| true) {
+                Some(summary) if summary.size == file.size && summary.modified == file.modified => None,
+                Some(_) => Some("Содержимое каталога изменилось после сканирования".to_string()),
+                None => Some("Не удалось прочитать каталог".to_string()),
+            };
+        }
+
+        if metadata.len() != file.size {
+            return Some("Размер файла изменился после сканирования".to_string());
+        }
+
+        let modified = metadata
+            .modified()
+            .unwrap_or(UNIX_EPOCH)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+
+        if modified != file.modified {
+            return Some("Файл изменился после сканирования".to_string());
+        }
+
+        None
+    }
+
+    fn is_excluded(&self, path: &Path, relative: &Path, exclusions: &PatternSet) -> bool {
+        self.config.excluded_paths.iter().any(|excluded| {
+            path.starts_with(excluded)
+        }) || exclusions.is_match(relative)
+    }
+
+    /// Проверяет, что возраст файла (от текущего момента) попадает в границы категории
+    fn is_within_age(&self, timestamp: SystemTime, category: &CleanupCategory) -> bool {
+        if category.min_age_days == 0 && category.max_age_days.is_none() {
+            return true;
+        }
+
+        let age = SystemTime::now()
+            .duration_since(timestamp)
+            .unwrap_or(Duration::from_secs(0));
+
+        let min_age = Duration::from_secs(category.min_age_days as u64 * 24 * 60 * 60);
+        if age < min_age {
+            return false;
+        }
+
+        match category.max_age_days {
+            Some(max_age_days) => age <= Duration::from_secs(max_age_days as u64 * 24 * 60 * 60),
+            None => true,
+        }
+    }
+
+    /// Удаляет каталоги, опустевшие после очистки, поднимаясь вверх до корня категории (сам корень не трогается)
+    fn prune_empty_dirs(&self, roots: &[PathBuf], mut dirs: Vec<PathBuf>) -> usize {
+        let mut pruned = 0;
+        let mut visited = HashSet::new();
+
+        // Сначала самые глубокие, чтобы родитель освобождался после своих потомков
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+        for dir in dirs {
+            let mut current = dir;
+            while visited.insert(current.clone()) {
+                let inside_root = roots
+                    .iter()
+                    .any(|root| current.starts_with(root) && current != *root);
+                if !inside_root || std::fs::remove_dir(&current).is_err() {
+                    break;
+                }
+                pruned += 1;
+
+                match current.parent() {
+                    Some(parent) => current = parent.to_path_buf(),
+                    None => break,
+                }
+            }
+        }
+
+        pruned
+    }
+
+    /// Считает занятое место в произвольном каталоге или на разделе. Только читает диск,
+    /// поэтому ни исключения, ни защищённые пути не применяются
+    pub fn analyze_usage(&self, path: &Path, options: &UsageOptions, job: &Job) -> Result<serde_json::Value, CleanerError> {
+        let report = self.build_pool()?.install(|| analyzer::analyze(path, options, job))?;
+        Ok(serde_json::to_value(report)?)
+    }
+
+    pub fn list_quarantine_sessions(&self) -> Result<serde_json::Value, CleanerError> {
+        Ok(serde_json::to_value(quarantine::list_sessions()?)?)
+    }
+
+    pub fn restore_quarantine(&self, session_id: &str, paths: &[String]) -> Result<serde_json::Value, CleanerError> {
+        Ok(serde_json::to_value(quarantine::restore(session_id, paths)?)?)
+    }
+
+    pub fn purge_quarantine(&self, retention_days: Option<u32>) -> Result<serde_json::Value, CleanerError> {
+        let retention_days = retention_days.unwrap_or(self.config.quarantine_retention_days);
+        Ok(serde_json::to_value(quarantine::purge(retention_days)?)?)
+    }
+}
+
+/// Возвращает отметку времени, по которой категория считает возраст файла
+fn file_timestamp(metadata: &Metadata, kind: AgeTimestamp, noatime: bool) -> SystemTime {
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+
+    match kind {
+        AgeTimestamp::Modified => modified,
+        // Файл не мог быть прочитан раньше, чем записан, поэтому устаревший atime (relatime) не делает его старше
+        AgeTimestamp::Accessed if noatime => modified,
+        AgeTimestamp::Accessed => metadata.accessed().map_or(modified, |accessed| accessed.max(modified)),
+        AgeTimestamp::Created => metadata.created().unwrap_or(modified),
+        AgeTimestamp::Changed => changed_time(metadata).unwrap_or(modified),
+    }
+}
+
+/// Кандидат для индекса: файл и отметка времени, по которой категория считает его возраст
+fn indexed_file(path: &Path, metadata: &Metadata, kind: AgeTimestamp, noatime: bool) -> IndexedFile {
+    let timestamp = file_timestamp(metadata, kind, noatime);
+    IndexedFile {
+        file: FileInfo::from_metadata(path, metadata),
+        timestamp: timestamp.duration_since(UNIX_EPOCH).map_or(0, |age| age.as_secs()),
+    }
+}
+
+#[cfg(unix)]
+fn changed_time(metadata: &Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+
+    let secs = u64::try_from(metadata.ctime()).ok()?;
+    Some(UNIX_EPOCH + Duration::new(secs, metadata.ctime_nsec() as u32))
+}
+
+#[cfg(not(unix))]
+fn changed_time(_metadata: &Metadata) -> Option<SystemTime> {
+    None
+}
+
+/// Ссылка ведёт в один из своих же родительских каталогов. Взаимные петли через несколько ссылок не видит
+fn is_symlink_loop(link: &Path) -> bool {
+    let target = match link.canonicalize() {
+        Ok(target) => target,
+        Err(_) => return true,
+    };
+
+    link.parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .is_some_and(|parent| parent.starts_with(&target))
+}
+
+/// Время изменения с точностью до наносекунд, как его хранит индекс сканирования
+fn modified_parts(metadata: &Metadata) -> Option<(u64, u32)> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((modified.as_secs(), modified.subsec_nanos()))
+}
+
+/// Место на диске, которое занимает файл. Разреженные и сжатые файлы занимают меньше своей длины
+#[cfg(unix)]
+pub(crate) fn allocated_size(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    // st_blocks всегда считается в 512-байтных блоках, независимо от размера блока файловой системы
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+pub(crate) fn allocated_size(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+#[cfg(unix)]
+pub(crate) fn file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(FileId {
+        device: metadata.dev(),
+        inode: metadata.ino(),
+    })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn file_id(_metadata: &Metadata) -> Option<FileId> {
+    None
+}
+
+#[cfg(unix)]
+pub(crate) fn link_count(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn link_count(_metadata: &Metadata) -> u64 {
+    1
+}
+
+/// Пользователь, от имени которого работает программа. Вне unix владельцев у файлов нет
+#[cfg(unix)]
+pub(crate) fn current_uid() -> Option<u32> {
+    // SAFETY: getuid не имеет побочных эффектов и всегда успешен
+    Some(unsafe { libc::getuid() })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn current_uid() -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn is_owned(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    current_uid() == Some(metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn is_owned(_metadata: &Metadata) -> bool {
+    true
+}
+
+/// Итоги по каталогу, который удаляется целиком
+struct DirSummary {
+    size: u64,
+    /// Место, которое освободится при удалении каталога: файлы с жёсткими ссылками снаружи не учитываются
+    allocated: u64,
+    /// Самая поздняя дата изменения внутри каталога (секунды с UNIX_EPOCH)
+    modified: u64,
+    /// Самая поздняя отметка времени выбранного категорией типа
+    newest: SystemTime,
+}
+
+/// Обходит каталог целиком. Возвращает `None`, если что-то не читается или `allow` отверг один из путей
+fn summarize_dir(
+    dir: &Path,
+    kind: AgeTimestamp,
+    noatime: bool,
+    mut allow: impl FnMut(&Path) -> bool,
+) -> Option<DirSummary> {
+    let mut summary = DirSummary {
+        size: 0,
+        allocated: 0,
+        modified: 0,
+        newest: UNIX_EPOCH,
+    };
+
+    // Для файлов с несколькими ссылками: сколько ссылок найдено внутри каталога и всего у файла
+    let mut linked: HashMap<FileId, (u64, u64, u64)> = HashMap::new();
+    let mut device = None;
+
+    for entry in WalkDir::new(dir).follow_links(false) {
+        let entry = entry.ok()?;
+        if entry.depth() > 0 && !allow(entry.path()) {
+            return None;
+        }
+
+        let metadata = entry.metadata().ok()?;
+
+        // remove_dir_all не остановится на точке монтирования, поэтому такой каталог целиком не берём
+        let entry_device = file_id(&metadata).map(|id| id.device);
+        if entry.depth() == 0 {
+            device = entry_device;
+        } else if entry_device != device {
+            return None;
+        }
+
+        if metadata.is_file() {
+            summary.size += metadata.len();
+
+            let links = link_count(&metadata);
+            match file_id(&metadata) {
+                Some(id) if links > 1 => {
+                    let (seen, _, _) = linked.entry(id).or_insert((0, links, allocated_size(&metadata)));
+                    *seen += 1;
+                }
+                _ => summary.allocated += allocated_size(&metadata),
+            }
+        }
+
+        let modified = metadata
+            .modified()
+            .unwrap_or(UNIX_EPOCH)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        summary.modified = summary.modified.max(modified);
+        summary.newest = summary.newest.max(file_timestamp(&metadata, kind, noatime));
+    }
+
+    summary.allocated += linked
+        .values()
+        .filter(|(seen, links, _)| seen >= links)
+        .map(|(_, _, allocated)| allocated)
+        .sum::<u64>();
+
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{JobKind, JobManager};
+    use std::time::Instant;
+
+    /// Генерирует дерево `fanout^depth` каталогов с `files_per_dir` файлами в каждом.
+    /// Половина файлов подходит под шаблон `*.tmp`, размеры разные, чтобы сравнение итогов что-то значило
+    fn generate_tree(root: &Path, depth: usize, fanout: usize, files_per_dir: usize) {
+        std::fs::create_dir_all(root).unwrap();
+        for index in 0..files_per_dir {
+            let extension = if index % 2 == 0 { "tmp" } else { "dat" };
+            std::fs::write(root.join(format!("file{}.{}", index, extension)), vec![0u8; index * 100]).unwrap();
+        }
+        if depth > 0 {
+            for index in 0..fanout {
+                generate_tree(&root.join(format!("dir{}", index)), depth - 1, fanout, files_per_dir);
+            }
+        }
+    }
+
+    fn fixture_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("cleaner-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        root
+    }
+
+    fn service(root: &Path, scan_threads: usize) -> CleanerService {
+        let category = CleanupCategory {
+            enabled: true,
+            paths: vec![root.to_path_buf()],
+            file_patterns: vec!["*.tmp".to_string()],
+            ..Default::default()
+        };
+
+        CleanerService::new(AppConfig {
+            cleanup_categories: HashMap::from([("fixture".to_string(), category)]),
+            safe_mode: false,
+            scan_threads,
+            ..Default::default()
+        })
+    }
+
+    /// Сканирует категорию фикстуры на пуле из `scan_threads` потоков
+    fn scan_indexed(service: &CleanerService, index: Option<&ScanIndex>) -> (ScanResult, CategoryIndex) {
+        let job = JobManager::default().create(JobKind::Scan);
+        let category = &service.config.cleanup_categories["fixture"];
+        let pool = service.build_pool().unwrap();
+        pool.install(|| service.scan_category("fixture", category, &job, index)).unwrap()
+    }
+
+    fn scan(service: &CleanerService) -> ScanResult {
+        scan_indexed(service, None).0
+    }
+
+    /// Ставит время доступа и изменения в секундах от начала эпохи
+    #[cfg(unix)]
+    fn set_times(path: &Path, seconds: i64) {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+        let times = [libc::timeval { tv_sec: seconds as libc::time_t, tv_usec: 0 }; 2];
+        // SAFETY: путь — корректная C-строка, массив содержит ровно два значения времени
+        assert_eq!(unsafe { libc::utimes(path.as_ptr(), times.as_ptr()) }, 0);
+    }
+
+    /// Сдвигает время изменения в прошлое, чтобы каталог попал в индекс и выглядел неизменившимся
+    #[cfg(unix)]
+    fn backdate(path: &Path) {
+        set_times(path, 1_500_000_000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_stops_at_mutual_symlink_loops() {
+        let root = fixture_root("loops");
+        for (dir, link, target) in [("a", "l1", "../b"), ("b", "l2", "../a")] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join(format!("{}.tmp", dir)), b"data").unwrap();
+            std::os::unix::fs::symlink(target, root.join(dir).join(link)).unwrap();
+        }
+
+        let mut service = service(&root, 1);
+        service.config.cleanup_categories.get_mut("fixture").unwrap().symlinks = SymlinkPolicy::Follow;
+        let result = scan(&service);
+
+        let mut loops: Vec<&str> = result.cut_off.iter()
+            .filter(|cut_off| cut_off.reason == CutOffReason::SymlinkLoop)
+            .map(|cut_off| cut_off.path.strip_prefix(root.to_str().unwrap()).unwrap())
+            .collect();
+        loops.sort();
+        assert_eq!(loops, ["/a/l1/l2", "/b/l2/l1"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directory_is_reported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = fixture_root("unreadable");
+        let locked = root.join("locked");
+        std::fs::create_dir_all(&locked).unwrap();
+        std::fs::write(locked.join("a.tmp"), b"data").unwrap();
+        std::fs::write(root.join("b.tmp"), b"data").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Суперпользователь читает каталог и без прав, проверять тогда нечего
+        if std::fs::read_dir(&locked).is_err() {
+            let result = scan(&service(&root, 1));
+            assert_eq!(result.total_files, 1);
+            assert_eq!(result.errors.len(), 1);
+            assert!(result.errors[0].starts_with(locked.to_str().unwrap()), "{}", result.errors[0]);
+        }
+
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn indexed_scan_restats_files() {
+        let root = fixture_root("restat");
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        std::fs::write(root.join("dir/a.tmp"), b"old").unwrap();
+        backdate(&root.join("dir"));
+        backdate(&root);
+
+        let service = service(&root, 1);
+        let (first, category_index) = scan_indexed(&service, None);
+        assert_eq!(first.files[0].size, 3);
+        let mut index = ScanIndex::default();
+        index.replace(HashMap::from([("fixture".to_string(), category_index)]));
+
+        // Содержимое переписано, новый файл спрятан от индекса прежним временем изменения каталога
+        std::fs::write(root.join("dir/a.tmp"), b"rewritten").unwrap();
+        std::fs::write(root.join("dir/b.tmp"), b"new").unwrap();
+        backdate(&root.join("dir"));
+
+        let (second, _) = scan_indexed(&service, Some(&index));
+        let files: Vec<(&str, u64)> = second.files.iter().map(|file| (file.path.as_str(), file.size)).collect();
+        assert_eq!(files, [(root.join("dir/a.tmp").to_str().unwrap(), 9)]);
+
+        // Категория по времени доступа индекс не использует и видит новый файл
+        let mut service = service;
+        service.config.cleanup_categories.get_mut("fixture").unwrap().age_timestamp = AgeTimestamp::Accessed;
+        let fingerprint = scan_index::fingerprint(&service.config, &service.config.cleanup_categories["fixture"]);
+        let (accessed, _) = scan_indexed(&service, Some(&index));
+        assert_eq!(accessed.total_files, 2);
+        assert!(index.category("fixture", &fingerprint).is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Очищает категорию фикстуры по плану последнего сканирования
+    fn clean(service: &mut CleanerService, dry_run: bool) -> CleanResult {
+        let job = JobManager::default().create(JobKind::Clean);
+        let report = service.clean_categories(vec!["fixture".to_string()], dry_run, &job).unwrap();
+        let mut results: Vec<CleanResult> = serde_json::from_value(report).unwrap();
+        results.remove(0)
+    }
+
+    #[test]
+    fn clean_skips_files_changed_or_added_after_scan() {
+        let root = fixture_root("plan");
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        for name in ["a.tmp", "b.tmp", "dir/c.tmp", "keep.dat"] {
+            std::fs::write(root.join(name), b"data").unwrap();
+        }
+
+        let mut service = service(&root, 1);
+        let plan = scan(&service);
+        assert_eq!(plan.total_files, 3);
+        service.scan_results.insert("fixture".to_string(), plan);
+
+        std::fs::write(root.join("a.tmp"), b"changed").unwrap();
+        std::fs::write(root.join("dir/new.tmp"), b"data").unwrap();
+
+        let result = clean(&mut service, false);
+
+        let skipped: Vec<(&str, &str)> = result.skipped
+            .iter()
+            .map(|file| (file.path.strip_prefix(root.to_str().unwrap()).unwrap(), file.reason.as_str()))
+            .collect();
+        assert_eq!(skipped, [
+            ("/a.tmp", "Размер файла изменился после сканирования"),
+            ("/dir/new.tmp", "Файл появился после сканирования"),
+        ]);
+        assert_eq!(result.files_removed, 2);
+        assert!(result.errors.is_empty());
+        for (name, exists) in [("a.tmp", true), ("b.tmp", false), ("dir/c.tmp", false), ("dir/new.tmp", true), ("keep.dat", true)] {
+            assert_eq!(root.join(name).exists(), exists, "{}", name);
+        }
+
+        // В плане остаётся только то, что ещё лежит на диске
+        assert_eq!(service.scan_results["fixture"].total_files, 1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn age_is_measured_from_now_within_bounds() {
+        const HOUR: i64 = 60 * 60;
+        const DAY: i64 = 24 * HOUR;
+
+        let root = fixture_root("age");
+        std::fs::create_dir_all(&root).unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let ages = [
+            ("future.tmp", -DAY),
+            ("fresh.tmp", HOUR),
+            ("below_min.tmp", 10 * DAY - HOUR),
+            ("above_min.tmp", 10 * DAY + HOUR),
+            ("middle.tmp", 20 * DAY),
+            ("below_max.tmp", 30 * DAY - HOUR),
+            ("above_max.tmp", 30 * DAY + HOUR),
+            ("ancient.tmp", 3650 * DAY),
+        ];
+        for (name, age) in ages {
+            std::fs::write(root.join(name), b"data").unwrap();
+            set_times(&root.join(name), now - age);
+        }
+
+        let mut service = service(&root, 1);
+        let names = |service: &CleanerService| -> Vec<String> {
+            scan(service).files.iter().map(|file| file.path.rsplit('/').next().unwrap().to_string()).collect()
+        };
+        assert_eq!(names(&service).len(), ages.len());
+
+        let category = service.config.cleanup_categories.get_mut("fixture").unwrap();
+        category.min_age_days = 10;
+        assert_eq!(names(&service), ["above_max.tmp", "above_min.tmp", "ancient.tmp", "below_max.tmp", "middle.tmp"]);
+
+        let category = service.config.cleanup_categories.get_mut("fixture").unwrap();
+        category.max_age_days = Some(30);
+        assert_eq!(names(&service), ["above_min.tmp", "below_max.tmp", "middle.tmp"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn dry_run_reports_the_plan_and_touches_nothing() {
+        let root = fixture_root("dry-run");
+        generate_tree(&root, 2, 3, 4);
+        let snapshot = || -> Vec<(PathBuf, u64, SystemTime)> {
+            WalkDir::new(&root)
+                .sort_by_file_name()
+                .into_iter()
+                .map(|entry| {
+                    let entry = entry.unwrap();
+                    let metadata = entry.metadata().unwrap();
+                    (entry.into_path(), metadata.len(), metadata.modified().unwrap())
+                })
+                .collect()
+        };
+
+        let mut service = service(&root, 1);
+        let plan = scan(&service);
+        let before = snapshot();
+        let result = clean(&mut service, true);
+
+        assert!(result.dry_run);
+        assert_eq!(result.files_removed, plan.total_files);
+        assert_eq!(result.space_freed, plan.total_size);
+        let would_remove: Vec<&str> = result.would_remove.iter().map(|file| file.path.as_str()).collect();
+        let planned: Vec<&str> = plan.files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(would_remove, planned);
+        assert_eq!(snapshot(), before);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn trashed_files_are_counted_apart_from_removed() {
+        let root = fixture_root("trash");
+        let data_home = fixture_root("trash-data");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.tmp"), b"data").unwrap();
+        // Домашняя корзина берётся из XDG_DATA_HOME и лежит на том же разделе, что и фикстура.
+        // Другие тесты корзину не используют
+        std::env::set_var("XDG_DATA_HOME", &data_home);
+
+        let mut service = service(&root, 1);
+        service.config.cleanup_categories.get_mut("fixture").unwrap().trash = true;
+        let plan = scan(&service);
+        service.scan_results.insert("fixture".to_string(), plan);
+        let result = clean(&mut service, false);
+
+        assert_eq!((result.files_trashed, result.files_removed), (1, 0));
+        assert!(result.space_trashed > 0);
+        assert_eq!(result.space_freed, 0);
+        assert!(!root.join("a.tmp").exists());
+
+        let trash = data_home.join("Trash");
+        assert_eq!(std::fs::read(trash.join("files/a.tmp")).unwrap(), b"data");
+        let info = std::fs::read_to_string(trash.join("info/a.tmp.trashinfo")).unwrap();
+        let lines: Vec<&str> = info.lines().collect();
+        assert_eq!(lines[..2], ["[Trash Info]", format!("Path={}", root.join("a.tmp").display()).as_str()]);
+        let deleted_at = lines[2].strip_prefix("DeletionDate=").unwrap();
+        let deleted_at = chrono::NaiveDateTime::parse_from_str(deleted_at, "%Y-%m-%dT%H:%M:%S").unwrap();
+        assert!((chrono::Local::now().naive_local() - deleted_at).num_seconds().abs() < 60);
+
+        std::fs::remove_dir_all(&root).unwrap();
+        std::fs::remove_dir_all(&data_home).unwrap();
+    }
+
+    fn linked_file(path: &str, inode: Option<u64>, links: u64) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size: 4096,
+            allocated: 4096,
+            file_id: inode.map(|inode| FileId { device: 1, inode }),
+            links,
+            modified: 0,
+            is_directory: false,
+        }
+    }
+
+    fn scan_result(category: &str, files: Vec<FileInfo>) -> ScanResult {
+        let mut result = ScanResult {
+            category: category.to_string(),
+            total_files: 0,
+            total_size: 0,
+            files,
+            errors: Vec::new(),
+            skipped_by_size: 0,
+            skipped_by_size_bytes: 0,
+            overlaps: Vec::new(),
+            cut_off: Vec::new(),
+            duplicates: Vec::new(),
+            mount: None,
+            scanned_at: None,
+        };
+        result.recount();
+        result
+    }
+
+    #[test]
+    fn recount_counts_space_when_last_link_goes() {
+        let result = scan_result("cache", vec![
+            // Вторая ссылка вне плана: место не освободится
+            linked_file("/cache/a", Some(1), 2),
+            // Обе ссылки в плане: место считается один раз
+            linked_file("/cache/b", Some(2), 2),
+            linked_file("/cache/c", Some(2), 2),
+            linked_file("/cache/d", Some(3), 1),
+            linked_file("/cache/e", None, 1),
+        ]);
+
+        assert_eq!(result.total_files, 5);
+        assert_eq!(result.total_size, 3 * 4096);
+    }
+
+    #[test]
+    fn scan_totals_join_links_across_categories() {
+        let mut service = CleanerService::new(AppConfig::default());
+        for (category, path) in [("cache", "/cache/a"), ("logs", "/logs/a")] {
+            let result = scan_result(category, vec![linked_file(path, Some(1), 2)]);
+            assert_eq!(result.total_size, 0);
+            service.scan_results.insert(category.to_string(), result);
+        }
+
+        let both = service.scan_totals(None).unwrap();
+        assert_eq!(both["total_files"], 2);
+        assert_eq!(both["total_size"], 4096);
+
+        let one = service.scan_totals(Some(&["cache".to_string()])).unwrap();
+        assert_eq!(one["total_size"], 0);
+    }
+
+    #[test]
+    fn parallel_scan_matches_sequential() {
+        let root = fixture_root("parallel");
+        generate_tree(&root, 3, 4, 6);
+
+        let sequential = scan(&service(&root, 1));
+        let parallel = scan(&service(&root, 0));
+
+        assert_eq!(sequential.total_files, 85 * 3);
+        assert_eq!(
+            serde_json::to_value(&sequential).unwrap(),
+            serde_json::to_value(&parallel).unwrap()
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Замер на большом дереве: `cargo test --release -- --ignored scan_speedup --nocapture`
+    #[test]
+    #[ignore]
+    fn scan_speedup() {
+        let root = fixture_root("speedup");
+        generate_tree(&root, 4, 8, 12);
+
+        let timed = |scan_threads: usize| {
+            let service = service(&root, scan_threads);
+            let started = Instant::now();
+            let result = scan(&service);
+            (started.elapsed(), result)
+        };
+
+        // Первый проход прогревает кеш страниц, чтобы оба замера читали метаданные из памяти
+        timed(0);
+        let (sequential_time, sequential) = timed(1);
+        let (parallel_time, parallel) = timed(0);
+
+        eprintln!(
+            "{} файлов: 1 поток — {:?}, все ядра — {:?}",
+            sequential.total_files, sequential_time, parallel_time
+        );
+        assert_eq!(
+            serde_json::to_value(&sequential).unwrap(),
+            serde_json::to_value(&parallel).unwrap()
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
     }
 }
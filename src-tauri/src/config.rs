@@ -1,268 +1,692 @@
+use crate::browsers;
+use crate::cleaner::current_uid;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub safe_mode: bool,
     pub backup_enabled: bool,
+    #[serde(default = "default_quarantine_retention_days")]
+    pub quarantine_retention_days: u32,
     pub max_file_size_mb: u64,
+    /// Число потоков сканирования, 0 — по числу ядер
+    #[serde(default)]
+    pub scan_threads: usize,
     pub excluded_paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub excluded_patterns: Vec<String>,
     pub cleanup_categories: HashMap<String, CleanupCategory>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CleanupCategory {
     pub enabled: bool,
     pub paths: Vec<PathBuf>,
     pub file_patterns: Vec<String>,
     pub min_age_days: u32,
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    #[serde(default)]
+    pub age_timestamp: AgeTimestamp,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// После очистки удалять каталоги, оставшиеся пустыми внутри корней категории
+    #[serde(default)]
+    pub prune_empty_dirs: bool,
+    #[serde(default)]
+    pub min_size: Option<ByteSize>,
+    /// Если не задан, действует глобальный `max_file_size_mb`
+    #[serde(default)]
+    pub max_size: Option<ByteSize>,
+    /// Кому достаётся файл, если корни категорий пересекаются: побеждает больший приоритет,
+    /// при равенстве — категория с более глубоким корнем
+    #[serde(default)]
+    pub priority: i32,
+    /// Глубина обхода от корня категории; если не задана, ограничения нет
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub symlinks: SymlinkPolicy,
+    /// Не переходить на другие файловые системы, смонтированные внутри корня
+    #[serde(default)]
+    pub one_file_system: bool,
+    /// Если задано, категория ищет файлы с одинаковым содержимым и удаляет лишние копии
+    #[serde(default)]
+    pub duplicates: Option<DuplicateSettings>,
+    /// Если задано, содержимое файлов перед удалением затирается, а карантин для категории не используется
+    #[serde(default)]
+    pub shred: Option<ShredSettings>,
+    /// Перемещать файлы в корзину по спецификации freedesktop.org вместо удаления. При `shred` не действует
+    #[serde(default)]
+    pub trash: bool,
+    /// Категория очищает корзины freedesktop.org: домашнюю и корзины в корнях всех разделов.
+    /// Элементы берутся из `files/` вместе с описаниями, возраст считается по `DeletionDate`
+    #[serde(default)]
+    pub trash_bin: bool,
+    /// Брать только файлы текущего пользователя и не спускаться в чужие каталоги — для общих каталогов вроде /tmp
+    #[serde(default)]
+    pub owned_by_user: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShredSettings {
+    /// Сколько раз перезаписать содержимое случайными данными
+    #[serde(default = "default_shred_passes")]
+    pub passes: u32,
+}
+
+impl Default for ShredSettings {
+    fn default() -> Self {
+        Self {
+            passes: default_shred_passes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateSettings {
+    #[serde(default)]
+    pub keep: KeepRule,
+    /// Заменять лишние копии жёсткими ссылками на оставляемую вместо удаления
+    #[serde(default)]
+    pub hardlink: bool,
+}
+
+/// Какую копию из набора дубликатов оставить
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeepRule {
+    /// Самую старую по времени изменения
+    #[default]
+    Oldest,
+    Newest,
+    /// Лежащую под корнем, который стоит в `paths` раньше остальных
+    PathPriority,
+}
+
+/// Какая отметка времени файла используется для расчёта его возраста
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeTimestamp {
+    #[default]
+    Modified,
+    Accessed,
+    Created,
+    Changed,
+}
+
+/// Что делать с символическими ссылками внутри корней категории
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Ссылки не раскрываются и не удаляются
+    #[default]
+    Skip,
+    /// Ссылки на каталоги обходятся как обычные подкаталоги
+    Follow,
+}
+
+/// Размер в байтах, который в конфигурации записывается в человеческих единицах: "500MB", "2GiB", "1.5 GB".
+/// KB/MB/GB/TB — десятичные единицы, KiB/MiB/GiB/TiB — двоичные
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "ByteSizeValue", into = "String")]
+pub struct ByteSize(pub u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ByteSizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+const BYTE_UNITS: [(&str, u64); 9] = [
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let unit = unit.trim();
+
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("Неверный размер: {}", s))?;
+
+        // Однобуквенные сокращения (K, M, G, T) считаем десятичными, как и KB/MB/GB/TB
+        let unit = match unit {
+            "" => "B".to_string(),
+            "k" | "K" | "m" | "M" | "g" | "G" | "t" | "T" => format!("{}B", unit),
+            _ => unit.to_string(),
+        };
+
+        let multiplier = BYTE_UNITS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&unit))
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(|| format!("Неизвестная единица размера: {}", s))?;
+
+        Ok(ByteSize((number * multiplier as f64).round() as u64))
+    }
+}
+
+impl TryFrom<ByteSizeValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: ByteSizeValue) -> Result<Self, Self::Error> {
+        match value {
+            ByteSizeValue::Bytes(bytes) => Ok(ByteSize(bytes)),
+            ByteSizeValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Записываем в самой крупной единице, которая делит размер без остатка
+        let (name, multiplier) = BYTE_UNITS
+            .iter()
+            .find(|(_, multiplier)| self.0 != 0 && self.0 / multiplier * multiplier == self.0)
+            .unwrap_or(&("B", 1));
+        write!(f, "{}{}", self.0 / multiplier, name)
+    }
+}
+
+impl From<ByteSize> for String {
+    fn from(size: ByteSize) -> Self {
+        size.to_string()
+    }
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
-        let mut categories = HashMap::new();
-        
-        // Временные файлы
-        categories.insert("temp_files".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_temp_paths(),
-            file_patterns: vec!["*.tmp".to_string(), "*.temp".to_string(), "*.bak".to_string()],
-            min_age_days: 0,
-        });
-        
-        // Кеш браузеров
-        categories.insert("browser_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_browser_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 7,
-        });
-        
-        // Логи системы
-        categories.insert("logs".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_log_paths(),
-            file_patterns: vec!["*.log".to_string(), "*.log.*".to_string()],
-            min_age_days: 30,
-        });
-        
-        // Корзина
-        categories.insert("recycle_bin".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_recycle_bin_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 0,
-        });
+        Self {
+            safe_mode: true,
+            backup_enabled: false,
+            quarantine_retention_days: default_quarantine_retention_days(),
+            max_file_size_mb: 100,
+            scan_threads: 0,
+            excluded_paths: vec![],
+            excluded_patterns: vec![],
+            cleanup_categories: if cfg!(target_os = "linux") {
+                linux_categories()
+            } else {
+                windows_categories()
+            },
+        }
+    }
+}
 
-        // Кеш Windows
-        categories.insert("windows_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_windows_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 7,
-        });
+/// Категории для Windows. На других системах, кроме Linux, тоже берётся этот набор
+fn windows_categories() -> HashMap<String, CleanupCategory> {
+    let mut categories = HashMap::new();
+    
+    // Временные файлы
+    categories.insert("temp_files".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_temp_paths(),
+        file_patterns: vec!["*.tmp".to_string(), "*.temp".to_string(), "*.bak".to_string()],
+        min_age_days: 0,
+        ..Default::default()
+    });
+    
+    // Кеш браузеров
+    categories.insert("browser_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: browsers::cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        ..Default::default()
+    });
+    
+    // Логи системы
+    categories.insert("logs".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_log_paths(),
+        file_patterns: vec!["*.log".to_string(), "*.log.*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
+    
+    // Корзина
+    categories.insert("recycle_bin".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_recycle_bin_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 0,
+        // Этот набор берётся и на BSD, где рабочие столы ведут корзину freedesktop.org.
+        // На macOS и Windows корзину ведёт система
+        trash_bin: cfg!(all(unix, not(target_os = "macos"))),
+        ..Default::default()
+    });
 
-        // Thumbnails
-        categories.insert("thumbnails".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_thumbnails_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Windows
+    categories.insert("windows_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_windows_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        ..Default::default()
+    });
 
-        // Downloads (старые файлы)
-        categories.insert("old_downloads".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_downloads_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 90,
-        });
+    // Thumbnails
+    categories.insert("thumbnails".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_thumbnails_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш приложений
-        categories.insert("app_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_app_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 14,
-        });
+    // Downloads (старые файлы)
+    categories.insert("old_downloads".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_downloads_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 90,
+        // Файлы пользователя: на BSD их можно достать из корзины freedesktop.org.
+        // Её не видят ни Finder, ни Проводник, поэтому на macOS и Windows файлы удаляются как обычно
+        trash: cfg!(all(unix, not(target_os = "macos"))),
+        ..Default::default()
+    });
 
-        // Временные установщики
-        categories.insert("installers".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_installer_paths(),
-            file_patterns: vec!["*.msi".to_string(), "*.exe".to_string(), "*.zip".to_string()],
-            min_age_days: 7,
-        });
+    // Дубликаты загрузок и медиафайлов. Выключено по умолчанию: удаляются файлы пользователя
+    categories.insert("duplicates".to_string(), CleanupCategory {
+        enabled: false,
+        paths: get_duplicates_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 0,
+        // Мелкие файлы не стоят времени на хеширование
+        min_size: Some(ByteSize(1 << 20)),
+        max_size: Some(ByteSize(64 << 30)),
+        duplicates: Some(DuplicateSettings {
+            keep: KeepRule::PathPriority,
+            hardlink: false,
+        }),
+        ..Default::default()
+    });
 
-        // Кеш Windows Update
-        categories.insert("windows_update".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_windows_update_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш приложений
+    categories.insert("app_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_app_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 14,
+        ..Default::default()
+    });
 
-        // Кеш Microsoft Store
-        categories.insert("microsoft_store".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_microsoft_store_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 14,
-        });
+    // Временные установщики
+    categories.insert("installers".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_installer_paths(),
+        file_patterns: vec!["*.msi".to_string(), "*.exe".to_string(), "*.zip".to_string()],
+        min_age_days: 7,
+        // Установщики бывают очень большими, именно их и нужно находить
+        max_size: Some(ByteSize(16 << 30)),
+        // Загрузки сканирует и old_downloads, установщики из них отдаём этой категории
+        priority: 1,
+        trash: cfg!(all(unix, not(target_os = "macos"))),
+        ..Default::default()
+    });
 
-        // Кеш Office
-        categories.insert("office_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_office_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Windows Update
+    categories.insert("windows_update".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_windows_update_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш Visual Studio
-        categories.insert("visual_studio".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_visual_studio_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 60,
-        });
+    // Кеш Microsoft Store
+    categories.insert("microsoft_store".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_microsoft_store_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 14,
+        // INetCache входит и в app_cache, эта категория точнее
+        priority: 1,
+        ..Default::default()
+    });
 
-        // Кеш .NET
-        categories.insert("dotnet_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_dotnet_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Office
+    categories.insert("office_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_office_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш Node.js
-        categories.insert("nodejs_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_nodejs_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 14,
-        });
+    // Кеш Visual Studio
+    categories.insert("visual_studio".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_visual_studio_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 60,
+        ..Default::default()
+    });
 
-        // Кеш Python
-        categories.insert("python_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_python_cache_paths(),
-            file_patterns: vec!["*".to_string(), "__pycache__".to_string()],
-            min_age_days: 14,
-        });
+    // Кеш .NET
+    categories.insert("dotnet_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_dotnet_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш Java
-        categories.insert("java_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_java_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Node.js
+    categories.insert("nodejs_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_nodejs_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 14,
+        ..Default::default()
+    });
 
-        // Кеш Adobe
-        categories.insert("adobe_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_adobe_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Python
+    categories.insert("python_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_python_cache_paths(),
+        file_patterns: vec!["*".to_string(), "__pycache__/".to_string()],
+        min_age_days: 14,
+        ..Default::default()
+    });
 
-        // Кеш NVIDIA
-        categories.insert("nvidia_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_nvidia_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Java
+    categories.insert("java_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_java_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш AMD
-        categories.insert("amd_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_amd_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш Adobe
+    categories.insert("adobe_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_adobe_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш Intel
-        categories.insert("intel_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_intel_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш NVIDIA
+    categories.insert("nvidia_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_nvidia_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш антивирусов
-        categories.insert("antivirus_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_antivirus_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 7,
-        });
+    // Кеш AMD
+    categories.insert("amd_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_amd_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш VPN
-        categories.insert("vpn_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_vpn_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 14,
-        });
+    // Кеш Intel
+    categories.insert("intel_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_intel_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
 
-        // Кеш торрентов
-        categories.insert("torrent_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_torrent_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 7,
-        });
+    // Кеш антивирусов
+    categories.insert("antivirus_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_antivirus_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        ..Default::default()
+    });
 
-        // Кеш медиаплееров
-        categories.insert("media_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_media_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 30,
-        });
+    // Кеш VPN
+    categories.insert("vpn_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_vpn_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 14,
+        ..Default::default()
+    });
 
-        // Кеш игр
-        categories.insert("games_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_games_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 60,
-        });
+    // Кеш торрентов
+    categories.insert("torrent_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_torrent_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        ..Default::default()
+    });
 
-        // Кеш системных утилит
-        categories.insert("system_utils".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_system_utils_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 14,
-        });
+    // Кеш медиаплееров
+    categories.insert("media_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_media_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
+
+    // Кеш игр
+    categories.insert("games_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_games_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 60,
+        ..Default::default()
+    });
+
+    // Кеш системных утилит
+    categories.insert("system_utils".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_system_utils_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 14,
+        ..Default::default()
+    });
+
+    // Кеш архиваторов
+    categories.insert("archivers_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_archivers_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 30,
+        ..Default::default()
+    });
+
+    // Кеш облачных хранилищ
+    categories.insert("cloud_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_cloud_cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 14,
+        ..Default::default()
+    });
+
+    categories
+}
+
+/// Что в `~/.cache` хранит состояние, а не только кеш. Удаление части файлов по возрасту
+/// здесь ломает данные: база остаётся без журнала, запись кеша без метаданных, блокировка
+/// исчезает из-под работающей программы
+const XDG_CACHE_EXCLUDE: [&str; 14] = [
+    // Файлы блокировок и базы SQLite в любом подкаталоге
+    "*.lock",
+    "*.lck",
+    "*.pid",
+    "*.sqlite",
+    "*.sqlite-*",
+    "*.db",
+    "*.db-*",
+    // Хранилища ключей и паролей
+    "/keyrings",
+    "/keepassxc",
+    // Кеш HTTP pip: тело ответа и его метаданные лежат в разных файлах
+    "/pip/http",
+    "/pip/http-v2",
+    // Индексы и офлайн-данные, которые программы не умеют частично восстанавливать
+    "/tracker",
+    "/tracker3",
+    "/evolution",
+];
+
+/// Категории для Linux. Каталоги пользователя берутся из переменных XDG Base Directory
+fn linux_categories() -> HashMap<String, CleanupCategory> {
+    let mut categories = HashMap::new();
+
+    // Кеш приложений: только содержимое подкаталогов, файлы-метки в корне кеша не трогаем.
+    // Каталоги с состоянием программ исключены, см. XDG_CACHE_EXCLUDE
+    categories.insert("xdg_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: xdg_home("XDG_CACHE_HOME", ".cache").into_iter().collect(),
+        file_patterns: vec!["*/**".to_string()],
+        exclude: XDG_CACHE_EXCLUDE.iter().map(|pattern| pattern.to_string()).collect(),
+        min_age_days: 14,
+        prune_empty_dirs: true,
+        one_file_system: true,
+        ..Default::default()
+    });
+
+    // Кеш браузеров частично лежит в ~/.cache, частично в профилях в ~/.config
+    categories.insert("browser_cache".to_string(), CleanupCategory {
+        enabled: true,
+        paths: browsers::cache_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        priority: 1,
+        ..Default::default()
+    });
 
-        // Кеш архиваторов
-        categories.insert("archivers_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_archivers_cache_paths(),
-            file_patterns: vec!["*".to_string()],
+    // Миниатюры лежат внутри кеша, эта категория точнее
+    categories.insert("thumbnails".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_linux_thumbnails_paths(),
+        file_patterns: vec!["*.png".to_string()],
+        min_age_days: 30,
+        priority: 1,
+        ..Default::default()
+    });
+
+    // Корзины freedesktop.org: домашняя и корзины на разделах находятся при сканировании
+    categories.insert("recycle_bin".to_string(), CleanupCategory {
+        enabled: true,
+        paths: vec![],
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 0,
+        trash_bin: true,
+        ..Default::default()
+    });
+
+    // Временные файлы пользователя в общих каталогах. Смонтированные внутри образы AppImage не трогаем
+    categories.insert("temp_files".to_string(), CleanupCategory {
+        enabled: true,
+        paths: vec![PathBuf::from("/tmp"), PathBuf::from("/var/tmp")],
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        one_file_system: true,
+        owned_by_user: true,
+        ..Default::default()
+    });
+
+    // Журнал X-сессии растёт без ограничений. Пока сессия пишет в файл, удаление места не освободит
+    categories.insert("xsession_errors".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_xsession_errors_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 7,
+        ..Default::default()
+    });
+
+    // Журналы и дампы называются по uid, а их каталоги принадлежат root: удалить файлы можно только
+    // с правами администратора, поэтому категории выключены по умолчанию
+    if let Some(uid) = current_uid() {
+        // Архивные журналы systemd текущего пользователя; активный журнал открыт journald
+        categories.insert("user_journals".to_string(), CleanupCategory {
+            enabled: false,
+            paths: vec![PathBuf::from("/var/log/journal")],
+            file_patterns: vec![format!("user-{}@*.journal", uid), format!("user-{}@*.journal~", uid)],
             min_age_days: 30,
+            max_size: Some(ByteSize(4 << 30)),
+            ..Default::default()
         });
 
-        // Кеш облачных хранилищ
-        categories.insert("cloud_cache".to_string(), CleanupCategory {
-            enabled: true,
-            paths: get_cloud_cache_paths(),
-            file_patterns: vec!["*".to_string()],
-            min_age_days: 14,
+        // Дампы памяти systemd-coredump: core.<программа>.<uid>.<загрузка>.<pid>.<время>
+        categories.insert("core_dumps".to_string(), CleanupCategory {
+            enabled: false,
+            paths: vec![PathBuf::from("/var/lib/systemd/coredump")],
+            file_patterns: vec![format!("core.*.{}.*", uid)],
+            min_age_days: 7,
+            max_size: Some(ByteSize(16 << 30)),
+            ..Default::default()
         });
-
-        Self {
-            safe_mode: true,
-            backup_enabled: false,
-            max_file_size_mb: 100,
-            excluded_paths: vec![],
-            cleanup_categories: categories,
-        }
     }
+
+    // Downloads (старые файлы)
+    categories.insert("old_downloads".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_downloads_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 90,
+        trash: true,
+        ..Default::default()
+    });
+
+    // Дубликаты загрузок и медиафайлов. Выключено по умолчанию: удаляются файлы пользователя
+    categories.insert("duplicates".to_string(), CleanupCategory {
+        enabled: false,
+        paths: get_duplicates_paths(),
+        file_patterns: vec!["*".to_string()],
+        min_age_days: 0,
+        min_size: Some(ByteSize(1 << 20)),
+        max_size: Some(ByteSize(64 << 30)),
+        duplicates: Some(DuplicateSettings {
+            keep: KeepRule::PathPriority,
+            hardlink: false,
+        }),
+        ..Default::default()
+    });
+
+    // Скачанные пакеты и установщики
+    categories.insert("installers".to_string(), CleanupCategory {
+        enabled: true,
+        paths: get_downloads_paths(),
+        file_patterns: vec!["*.deb".to_string(), "*.rpm".to_string(), "*.run".to_string(), "*.flatpakref".to_string()],
+        min_age_days: 7,
+        max_size: Some(ByteSize(16 << 30)),
+        // Загрузки сканирует и old_downloads, установщики из них отдаём этой категории
+        priority: 1,
+        trash: true,
+        ..Default::default()
+    });
+
+    categories
 }
 
 impl AppConfig {
@@ -290,7 +714,25 @@ impl AppConfig {
     }
 }
 
-fn get_config_path() -> PathBuf {
+/// Для современных дисков одного прохода достаточно, больше нужно только по требованиям регламентов
+fn default_shred_passes() -> u32 {
+    1
+}
+
+fn default_quarantine_retention_days() -> u32 {
+    30
+}
+
+/// Базовый каталог XDG: значение переменной, если это абсолютный путь (относительный спецификация велит
+/// игнорировать), иначе `fallback` относительно домашнего каталога
+pub fn xdg_home(var: &str, fallback: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(fallback)))
+}
+
+pub fn get_config_path() -> PathBuf {
     dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("cleaner")
@@ -316,27 +758,6 @@ fn get_temp_paths() -> Vec<PathBuf> {
     paths
 }
 
-fn get_browser_cache_paths() -> Vec<PathBuf> {
-    let mut paths = vec![];
-    
-    if let Some(data_dir) = dirs::data_dir() {
-        // Chrome
-        paths.push(data_dir.join("Google").join("Chrome").join("User Data").join("Default").join("Cache"));
-        // Firefox
-        paths.push(data_dir.join("Mozilla").join("Firefox").join("Profiles"));
-        // Edge
-        paths.push(data_dir.join("Microsoft").join("Edge").join("User Data").join("Default").join("Cache"));
-    }
-    
-    if let Some(cache_dir) = dirs::cache_dir() {
-        // Additional browser caches
-        paths.push(cache_dir.join("Google").join("Chrome"));
-        paths.push(cache_dir.join("Mozilla").join("Firefox"));
-    }
-    
-    paths
-}
-
 fn get_log_paths() -> Vec<PathBuf> {
     let mut paths = vec![];
     
@@ -398,6 +819,35 @@ fn get_downloads_paths() -> Vec<PathBuf> {
     paths
 }
 
+/// Миниатюры по спецификации freedesktop.org и устаревший каталог `~/.thumbnails`
+fn get_linux_thumbnails_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = xdg_home("XDG_CACHE_HOME", ".cache")
+        .map(|cache| cache.join("thumbnails"))
+        .into_iter()
+        .collect();
+
+    if let Some(home) = dirs::home_dir() {
+        paths.push(home.join(".thumbnails"));
+    }
+
+    paths
+}
+
+fn get_xsession_errors_paths() -> Vec<PathBuf> {
+    match dirs::home_dir() {
+        Some(home) => vec![home.join(".xsession-errors"), home.join(".xsession-errors.old")],
+        None => vec![],
+    }
+}
+
+/// Медиатеки идут раньше загрузок: при правиле `path_priority` копия в них остаётся
+fn get_duplicates_paths() -> Vec<PathBuf> {
+    [dirs::picture_dir(), dirs::audio_dir(), dirs::video_dir(), dirs::download_dir()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
 fn get_app_cache_paths() -> Vec<PathBuf> {
     let mut paths = vec![];
     
@@ -750,3 +1200,38 @@ fn get_cloud_cache_paths() -> Vec<PathBuf> {
     
     paths
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_size_parses_units() {
+        let parse = |text: &str| text.parse::<ByteSize>().map(|size| size.0);
+        assert_eq!(parse("512"), Ok(512));
+        assert_eq!(parse("500MB"), Ok(500_000_000));
+        assert_eq!(parse("2GiB"), Ok(2 << 30));
+        assert_eq!(parse(" 1.5 GB "), Ok(1_500_000_000));
+        assert_eq!(parse("10k"), Ok(10_000));
+        assert_eq!(parse("3kib"), Ok(3 << 10));
+        assert!(parse("5 parsecs").is_err());
+        assert!(parse("MB").is_err());
+    }
+
+    #[test]
+    fn byte_size_displays_in_largest_exact_unit() {
+        assert_eq!(ByteSize(0).to_string(), "0B");
+        assert_eq!(ByteSize(1 << 30).to_string(), "1GiB");
+        assert_eq!(ByteSize(3_000_000).to_string(), "3MB");
+        // Двоичные единицы проверяются первыми
+        assert_eq!(ByteSize(64_000_000).to_string(), "62500KiB");
+        assert_eq!(ByteSize(1001).to_string(), "1001B");
+    }
+
+    #[test]
+    fn byte_size_round_trips_through_json() {
+        let parsed: Vec<ByteSize> = serde_json::from_str(r#"[4096, "16GiB", "1.5KB"]"#).unwrap();
+        assert_eq!(parsed, [ByteSize(4096), ByteSize(16 << 30), ByteSize(1500)]);
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), r#"["4KiB","16GiB","1500B"]"#);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::cleaner::{translate_error, FileId, FileInfo};
+use crate::config::{DuplicateSettings, KeepRule};
+use crate::jobs::Job;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Сколько байт с начала файла хешируется на втором шаге
+const PARTIAL_HASH_BYTES: u64 = 64 * 1024;
+
+/// Файлы с одинаковым содержимым
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateSet {
+    pub size: u64,
+    /// SHA-256 содержимого
+    pub hash: String,
+    pub files: Vec<DuplicateFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateFile {
+    #[serde(flatten)]
+    pub file: FileInfo,
+    pub action: DuplicateAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateAction {
+    Keep,
+    Removable,
+}
+
+impl DuplicateSet {
+    pub fn kept(&self) -> Option<&FileInfo> {
+        self.files
+            .iter()
+            .find(|file| file.action == DuplicateAction::Keep)
+            .map(|file| &file.file)
+    }
+
+    pub fn removable(&self) -> impl Iterator<Item = &FileInfo> {
+        self.files
+            .iter()
+            .filter(|file| file.action == DuplicateAction::Removable)
+            .map(|file| &file.file)
+    }
+}
+
+/// Группирует файлы с одинаковым содержимым: сначала по размеру, затем по хешу начала файла
+/// и только потом по хешу всего файла. Целиком читаются лишь файлы, совпавшие на первых двух шагах.
+/// Вместе с наборами возвращает ошибки чтения
+pub fn find_duplicates(
+    files: Vec<FileInfo>,
+    settings: &DuplicateSettings,
+    roots: &[PathBuf],
+    job: &Job,
+) -> (Vec<DuplicateSet>, Vec<String>) {
+    // Жёсткие ссылки на один inode места не тратят, поэтому от каждого inode берём одну ссылку
+    let mut inodes = HashSet::new();
+    let files: Vec<FileInfo> = files
+        .into_iter()
+        .filter(|file| !file.is_directory && file.size > 0)
+        .filter(|file| match file.file_id {
+            Some(id) => inodes.insert(id),
+            None => true,
+        })
+        .collect();
+
+    let errors = std::sync::Mutex::new(Vec::new());
+    let hash = |file: &FileInfo, limit: Option<u64>| {
+        if job.is_cancelled() {
+            return None;
+        }
+        match hash_file(Path::new(&file.path), limit) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                job.error_seen();
+                errors.lock().unwrap().push(format!("{}: {}", file.path, translate_error(&e)));
+                None
+            }
+        }
+    };
+
+    let by_size = group_by(files.into_iter().map(|file| (file.size, file)));
+
+    let by_partial = group_by(
+        by_size
+            .into_par_iter()
+            .flat_map_iter(|(size, files)| files.into_iter().map(move |file| (size, file)))
+            .filter_map(|(size, file)| hash(&file, Some(PARTIAL_HASH_BYTES)).map(|partial| ((size, partial), file)))
+            .collect::<Vec<_>>(),
+    );
+
+    // Файл не длиннее проверенного начала уже прохеширован целиком
+    let by_content = group_by(
+        by_partial
+            .into_par_iter()
+            .flat_map_iter(|((size, partial), files)| {
+                files.into_iter().map(move |file| (size, partial.clone(), file))
+            })
+            .filter_map(|(size, partial, file)| {
+                let full = if size <= PARTIAL_HASH_BYTES { Some(partial) } else { hash(&file, None) };
+                full.map(|full| ((size, full), file))
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    let mut sets: Vec<DuplicateSet> = by_content
+        .into_iter()
+        .map(|((size, hash), files)| mark(size, hash, files, settings.keep, roots))
+        .collect();
+
+    sets.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.hash.cmp(&b.hash)));
+
+    let mut errors = errors.into_inner().unwrap();
+    errors.sort();
+    (sets, errors)
+}
+
+/// Раскладывает элементы по ключу и оставляет только группы из нескольких элементов
+fn group_by<K: Hash + Eq>(items: impl IntoIterator<Item = (K, FileInfo)>) -> Vec<(K, Vec<FileInfo>)> {
+    let mut groups: HashMap<K, Vec<FileInfo>> = HashMap::new();
+    for (key, file) in items {
+        groups.entry(key).or_default().push(file);
+    }
+    groups.into_iter().filter(|(_, files)| files.len() > 1).collect()
+}
+
+/// Выбирает оставляемую копию по правилу; при равенстве — с наименьшим путём
+fn mark(size: u64, hash: String, mut files: Vec<FileInfo>, keep: KeepRule, roots: &[PathBuf]) -> DuplicateSet {
+    let root_index = |file: &FileInfo| {
+        roots
+            .iter()
+            .position(|root| Path::new(&file.path).starts_with(root))
+            .unwrap_or(roots.len())
+    };
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let kept = match keep {
+        KeepRule::Oldest => files.iter().enumerate().min_by_key(|(_, file)| file.modified),
+        KeepRule::Newest => files.iter().enumerate().min_by_key(|(_, file)| std::cmp::Reverse(file.modified)),
+        KeepRule::PathPriority => files.iter().enumerate().min_by_key(|(_, file)| root_index(file)),
+    }
+    .map_or(0, |(index, _)| index);
+
+    let files = files
+        .into_iter()
+        .enumerate()
+        .map(|(index, file)| DuplicateFile {
+            file,
+            action: if index == kept { DuplicateAction::Keep } else { DuplicateAction::Removable },
+        })
+        .collect();
+
+    DuplicateSet { size, hash, files }
+}
+
+fn hash_file(path: &Path, limit: Option<u64>) -> io::Result<String> {
+    let file = File::open(path)?;
+    let mut reader: Box<dyn Read> = match limit {
+        Some(limit) => Box::new(file.take(limit)),
+        None => Box::new(file),
+    };
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Пути, которые удалит очистка выбранных категорий. Копию нельзя удалять,
+/// если вместе с ней будет удалён и оставляемый файл. По тем же путям считается место,
+/// которое освободится: файл с жёсткими ссылками освобождает его, только когда уходит последняя ссылка
+#[derive(Default)]
+pub(crate) struct PlannedRemovals {
+    files: HashSet<String>,
+    dirs: Vec<PathBuf>,
+    /// Сколько ссылок на inode входит в план и какая из них (с наименьшим путём) получает его место
+    links: HashMap<FileId, (u64, String)>,
+}
+
+impl PlannedRemovals {
+    pub fn add(&mut self, file: &FileInfo) {
+        if file.is_directory {
+            self.dirs.push(PathBuf::from(&file.path));
+        } else {
+            self.files.insert(file.path.clone());
+        }
+
+        if let Some(id) = file.file_id {
+            let (planned, owner) = self.links.entry(id).or_insert_with(|| (0, file.path.clone()));
+            *planned += 1;
+            if file.path < *owner {
+                *owner = file.path.clone();
+            }
+        }
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains(path) || self.dirs.iter().any(|dir| Path::new(path).starts_with(dir))
+    }
+
+    /// Место, которое освободит удаление файла из плана. Файл, все ссылки на который входят в план,
+    /// считается один раз — у одной из ссылок, в какой бы категории они ни лежали
+    pub fn reclaimed(&self, file: &FileInfo) -> u64 {
+        let id = match file.file_id {
+            Some(id) => id,
+            None => return file.allocated,
+        };
+
+        match self.links.get(&id) {
+            Some((planned, owner)) if *planned >= file.links && *owner == file.path => file.allocated,
+            _ => 0,
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a FileInfo> for PlannedRemovals {
+    fn from_iter<I: IntoIterator<Item = &'a FileInfo>>(files: I) -> Self {
+        let mut planned = Self::default();
+        for file in files {
+            planned.add(file);
+        }
+        planned
+    }
+}
+
+/// Атомарно заменяет `copy` жёсткой ссылкой на `original`: ссылка создаётся рядом и переименовывается поверх копии
+pub fn replace_with_link(original: &Path, copy: &Path) -> io::Result<()> {
+    let name = copy
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "путь без имени файла"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(".cleaner-link");
+    let temp = copy.with_file_name(temp_name);
+
+    std::fs::hard_link(original, &temp)?;
+    if let Err(e) = std::fs::rename(&temp, copy) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::{JobKind, JobManager};
+
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("duplicates-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&root);
+            std::fs::create_dir_all(&root).unwrap();
+            Self { root }
+        }
+
+        /// Создаёт файл и возвращает его описание с заданным временем изменения
+        fn file(&self, name: &str, content: &[u8], modified: u64) -> FileInfo {
+            let path = self.root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            self.info(name, modified)
+        }
+
+        fn info(&self, name: &str, modified: u64) -> FileInfo {
+            let path = self.root.join(name);
+            let metadata = std::fs::metadata(&path).unwrap();
+            FileInfo {
+                modified,
+                ..FileInfo::from_metadata(&path, &metadata)
+            }
+        }
+
+        fn find(&self, files: Vec<FileInfo>, keep: KeepRule, roots: &[PathBuf]) -> Vec<DuplicateSet> {
+            let settings = DuplicateSettings { keep, hardlink: false };
+            let job = JobManager::default().create(JobKind::Scan);
+            let (sets, errors) = find_duplicates(files, &settings, roots, &job);
+            assert!(errors.is_empty(), "{:?}", errors);
+            sets
+        }
+
+        fn name<'a>(&self, file: &'a FileInfo) -> &'a str {
+            Path::new(&file.path).strip_prefix(&self.root).unwrap().to_str().unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn groups_identical_content_only() {
+        let fixture = Fixture::new("groups");
+        let mut long = vec![7u8; PARTIAL_HASH_BYTES as usize + 10];
+        let files = vec![
+            fixture.file("a", b"same", 30),
+            fixture.file("b", b"same", 10),
+            fixture.file("c", b"diff", 20),
+            fixture.file("empty1", b"", 0),
+            fixture.file("empty2", b"", 0),
+            fixture.file("long1", &long, 0),
+            {
+                // То же начало, другой хвост: совпадёт на втором шаге, но не на третьем
+                *long.last_mut().unwrap() = 8;
+                fixture.file("long2", &long, 0)
+            },
+        ];
+
+        let sets = fixture.find(files, KeepRule::Oldest, &[]);
+
+        assert_eq!(sets.len(), 1);
+        let names: Vec<&str> = sets[0].files.iter().map(|file| fixture.name(&file.file)).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(sets[0].size, 4);
+        assert_eq!(fixture.name(sets[0].kept().unwrap()), "b");
+        let removable: Vec<&str> = sets[0].removable().map(|file| fixture.name(file)).collect();
+        assert_eq!(removable, ["a"]);
+    }
+
+    #[test]
+    fn keep_rules_choose_the_kept_copy() {
+        let fixture = Fixture::new("keep");
+        let files = vec![
+            fixture.file("first/x", b"content", 100),
+            fixture.file("second/x", b"content", 300),
+            fixture.file("second/y", b"content", 200),
+        ];
+        let roots = [fixture.root.join("second"), fixture.root.join("first")];
+
+        let kept = |keep: KeepRule| {
+            let sets = fixture.find(files.clone(), keep, &roots);
+            fixture.name(sets[0].kept().unwrap()).to_string()
+        };
+        assert_eq!(kept(KeepRule::Oldest), "first/x");
+        assert_eq!(kept(KeepRule::Newest), "second/x");
+        // Среди копий под первым корнем — с наименьшим путём
+        assert_eq!(kept(KeepRule::PathPriority), "second/x");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_not_duplicates() {
+        let fixture = Fixture::new("links");
+        let original = fixture.file("original", b"linked", 0);
+        std::fs::hard_link(fixture.root.join("original"), fixture.root.join("link")).unwrap();
+        let link = fixture.info("link", 0);
+
+        assert!(fixture.find(vec![original.clone(), link], KeepRule::Oldest, &[]).is_empty());
+
+        let copy = fixture.file("copy", b"linked", 0);
+        let sets = fixture.find(vec![original, copy], KeepRule::Oldest, &[]);
+        assert_eq!(sets.len(), 1);
+    }
+}
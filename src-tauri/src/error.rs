@@ -11,9 +11,27 @@ pub enum CleanerError {
     #[error("Invalid path: {path}")]
     InvalidPath { path: String },
     
+    #[error("Invalid pattern {pattern}: {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+    
     #[error("Cleanup failed: {category}")]
     CleanupFailed { category: String },
     
+    #[error("Job not found: {id}")]
+    JobNotFound { id: String },
+    
+    #[error("Operation cancelled")]
+    Cancelled,
+    
+    #[error("Job panicked: {reason}")]
+    JobPanicked { reason: String },
+    
+    #[error("Invalid ncdu dump: {reason}")]
+    InvalidDump { reason: String },
+    
+    #[error("Nothing to export: {reason}")]
+    NothingToExport { reason: String },
+    
     #[error("Configuration error: {0}")]
     Config(#[from] serde_json::Error),
     
@@ -0,0 +1,531 @@
+use crate::error::CleanerError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// Сколько завершённых задач хранить, чтобы их результаты можно было забрать
+const FINISHED_JOBS_LIMIT: usize = 32;
+
+/// Минимальный интервал между событиями прогресса одной задачи
+const EVENT_INTERVAL_MS: u64 = 100;
+
+/// Получатель событий прогресса (в приложении — отправка события в webview)
+pub type ProgressEmitter = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Scan,
+    Clean,
+    Analyze,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub id: String,
+    pub kind: JobKind,
+    pub state: JobState,
+    pub progress: f64,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Снимок прогресса задачи, который отправляется во фронтенд.
+/// При очистке `files_matched` и `bytes_matched` считают удалённые файлы и освобождённое место
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub state: JobState,
+    pub category: Option<String>,
+    pub current_dir: Option<String>,
+    pub files_seen: u64,
+    pub files_matched: u64,
+    pub bytes_matched: u64,
+    pub errors: u64,
+    pub progress: f64,
+    pub eta_seconds: Option<u64>,
+}
+
+/// Сколько работы ожидается от категории при сканировании
+#[derive(Debug, Clone, Copy)]
+pub struct ScanEstimate {
+    /// Каталогов в прошлом сканировании; 0 — сканирований ещё не было
+    pub expected_dirs: u64,
+    pub roots: usize,
+}
+
+/// Продвижение сканирования одной категории
+struct CategoryProgress {
+    estimate: ScanEstimate,
+    /// Доля категории в общей шкале
+    weight: f64,
+    dirs_seen: AtomicU64,
+    roots_done: AtomicU64,
+    done: AtomicBool,
+}
+
+impl CategoryProgress {
+    fn fraction(&self) -> f64 {
+        if self.done.load(Ordering::Relaxed) {
+            return 1.0;
+        }
+
+        // Каталогов может оказаться больше, чем в прошлый раз, поэтому до конца категории шкала не доходит
+        let ScanEstimate { expected_dirs, roots } = self.estimate;
+        if expected_dirs > 0 {
+            (self.dirs_seen.load(Ordering::Relaxed) as f64 / expected_dirs as f64).min(0.99)
+        } else if roots > 0 {
+            self.roots_done.load(Ordering::Relaxed) as f64 / roots as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+struct JobOutcome {
+    state: JobState,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+/// Фоновая задача сканирования или очистки. Прогресс и отмена доступны без блокировки сервиса
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    sequence: u64,
+    started_at: DateTime<Utc>,
+    started: Instant,
+    progress: AtomicU64,
+    cancelled: AtomicBool,
+    outcome: Mutex<JobOutcome>,
+    stage_index: AtomicUsize,
+    stage_count: AtomicUsize,
+    files_seen: AtomicU64,
+    files_matched: AtomicU64,
+    bytes_matched: AtomicU64,
+    errors: AtomicU64,
+    /// Время последнего события в миллисекундах от старта задачи
+    last_event_ms: AtomicU64,
+    /// Оценки объёма по категориям, если задача — сканирование
+    scan_progress: OnceLock<HashMap<String, CategoryProgress>>,
+    emitter: Option<ProgressEmitter>,
+}
+
+impl Job {
+    pub fn set_progress(&self, progress: f64) {
+        self.progress.store(progress.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn progress(&self) -> f64 {
+        f64::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    /// Делит шкалу прогресса на `count` равных этапов и переходит к этапу `index`
+    pub fn begin_stage(&self, index: usize, count: usize) {
+        self.stage_index.store(index, Ordering::Relaxed);
+        self.stage_count.store(count, Ordering::Relaxed);
+        self.stage_progress(0.0);
+    }
+
+    /// Прогресс внутри текущего этапа, от 0 до 1
+    pub fn stage_progress(&self, fraction: f64) {
+        let count = self.stage_count.load(Ordering::Relaxed).max(1);
+        let index = self.stage_index.load(Ordering::Relaxed);
+        self.set_progress((index as f64 + fraction.clamp(0.0, 1.0)) / count as f64);
+    }
+
+    /// Включает прогресс сканирования по каталогам: каждая категория продвигается по мере обхода
+    /// относительно прошлого сканирования, а без него — по обойдённым корням. Вес категории в общей
+    /// шкале пропорционален числу её каталогов; у категорий без оценки — средний
+    pub fn expect_scan(&self, estimates: HashMap<String, ScanEstimate>) {
+        let known: Vec<u64> = estimates
+            .values()
+            .map(|estimate| estimate.expected_dirs)
+            .filter(|&dirs| dirs > 0)
+            .collect();
+        let average = if known.is_empty() {
+            1.0
+        } else {
+            known.iter().sum::<u64>() as f64 / known.len() as f64
+        };
+
+        let progress = estimates
+            .into_iter()
+            .map(|(category, estimate)| {
+                let weight = if estimate.expected_dirs > 0 { estimate.expected_dirs as f64 } else { average };
+                (category, CategoryProgress {
+                    estimate,
+                    weight,
+                    dirs_seen: AtomicU64::new(0),
+                    roots_done: AtomicU64::new(0),
+                    done: AtomicBool::new(false),
+                })
+            })
+            .collect();
+        let _ = self.scan_progress.set(progress);
+    }
+
+    /// Обход одного корня категории закончен
+    pub fn root_done(&self, category: &str) {
+        if let Some(progress) = self.category_progress(category) {
+            progress.roots_done.fetch_add(1, Ordering::Relaxed);
+            self.update_scan_progress();
+        }
+    }
+
+    /// Категория просканирована целиком
+    pub fn category_done(&self, category: &str) {
+        if let Some(progress) = self.category_progress(category) {
+            progress.done.store(true, Ordering::Relaxed);
+            self.update_scan_progress();
+        }
+    }
+
+    fn category_progress(&self, category: &str) -> Option<&CategoryProgress> {
+        self.scan_progress.get()?.get(category)
+    }
+
+    fn update_scan_progress(&self) {
+        let categories = match self.scan_progress.get() {
+            Some(categories) => categories,
+            None => return,
+        };
+
+        let total: f64 = categories.values().map(|progress| progress.weight).sum();
+        if total <= 0.0 {
+            return;
+        }
+        let done: f64 = categories.values().map(|progress| progress.weight * progress.fraction()).sum();
+
+        // Потоки обновляют прогресс вперемешку; у неотрицательных f64 порядок битов совпадает с порядком
+        // чисел, поэтому fetch_max не даёт шкале откатиться назад
+        let progress = (done / total).clamp(0.0, 1.0);
+        self.progress.fetch_max(progress.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Задача зашла в каталог
+    pub fn dir_seen(&self, category: &str, dir: &Path) {
+        if let Some(progress) = self.category_progress(category) {
+            progress.dirs_seen.fetch_add(1, Ordering::Relaxed);
+            self.update_scan_progress();
+        }
+        self.maybe_emit(category, dir);
+    }
+
+    /// Задача просмотрела файл в каталоге `dir`
+    pub fn file_seen(&self, category: &str, dir: &Path) {
+        self.files_seen.fetch_add(1, Ordering::Relaxed);
+        self.maybe_emit(category, dir);
+    }
+
+    pub fn file_matched(&self, size: u64) {
+        self.files_matched.fetch_add(1, Ordering::Relaxed);
+        self.bytes_matched.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub fn error_seen(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Просит задачу остановиться. Задача доделывает текущий файл и завершается
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Выполняет работу задачи и фиксирует её итог. Паника в работе завершает задачу с ошибкой,
+    /// иначе задача осталась бы выполняющейся навсегда
+    pub fn run(&self, work: impl FnOnce() -> Result<serde_json::Value, CleanerError>) {
+        let result = panic::catch_unwind(AssertUnwindSafe(work)).unwrap_or_else(|payload| {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Err(CleanerError::JobPanicked { reason })
+        });
+        self.finish(result);
+    }
+
+    /// Фиксирует итог задачи. Частичный результат отменённой задачи сохраняется
+    pub fn finish(&self, result: Result<serde_json::Value, CleanerError>) {
+        let state = self.record_outcome(result);
+
+        // Итоговое событие отправляется всегда, независимо от ограничения частоты
+        if let Some(emitter) = &self.emitter {
+            emitter(&self.event(state, None, None));
+        }
+    }
+
+    fn record_outcome(&self, result: Result<serde_json::Value, CleanerError>) -> JobState {
+        let mut outcome = self.outcome.lock().unwrap();
+        outcome.finished_at = Some(Utc::now());
+
+        match result {
+            Ok(value) => {
+                outcome.state = if self.is_cancelled() {
+                    JobState::Cancelled
+                } else {
+                    self.set_progress(1.0);
+                    JobState::Completed
+                };
+                outcome.result = Some(value);
+            }
+            Err(CleanerError::Cancelled) => outcome.state = JobState::Cancelled,
+            Err(e) => {
+                outcome.state = JobState::Failed;
+                outcome.error = Some(e.to_string());
+            }
+        }
+
+        outcome.state
+    }
+
+    /// Отправляет событие, если с предыдущего прошло не меньше `EVENT_INTERVAL_MS`
+    fn maybe_emit(&self, category: &str, dir: &Path) {
+        let emitter = match &self.emitter {
+            Some(emitter) => emitter,
+            None => return,
+        };
+
+        let now = self.started.elapsed().as_millis() as u64;
+        let last = self.last_event_ms.load(Ordering::Relaxed);
+        if now < last + EVENT_INTERVAL_MS {
+            return;
+        }
+
+        // Из нескольких потоков событие отправляет только тот, кто первым сдвинул отметку
+        if self
+            .last_event_ms
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+
+        emitter(&self.event(JobState::Running, Some(category), Some(dir)));
+    }
+
+    fn event(&self, state: JobState, category: Option<&str>, dir: Option<&Path>) -> ProgressEvent {
+        let progress = self.progress();
+
+        // Оценка по доле выполненной работы: оставшаяся часть идёт с той же средней скоростью
+        let eta_seconds = if state == JobState::Running && progress > 0.0 {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            Some((elapsed * (1.0 - progress) / progress).round() as u64)
+        } else {
+            None
+        };
+
+        ProgressEvent {
+            job_id: self.id.clone(),
+            kind: self.kind,
+            state,
+            category: category.map(str::to_string),
+            current_dir: dir.map(|dir| dir.to_string_lossy().to_string()),
+            files_seen: self.files_seen.load(Ordering::Relaxed),
+            files_matched: self.files_matched.load(Ordering::Relaxed),
+            bytes_matched: self.bytes_matched.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            progress,
+            eta_seconds,
+        }
+    }
+
+    pub fn status(&self) -> JobStatus {
+        let outcome = self.outcome.lock().unwrap();
+        JobStatus {
+            id: self.id.clone(),
+            kind: self.kind,
+            state: outcome.state,
+            progress: self.progress(),
+            error: outcome.error.clone(),
+            started_at: self.started_at,
+            finished_at: outcome.finished_at,
+        }
+    }
+
+    pub fn result(&self) -> Option<serde_json::Value> {
+        self.outcome.lock().unwrap().result.clone()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.outcome.lock().unwrap().state != JobState::Running
+    }
+}
+
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, Arc<Job>>>,
+    next_id: AtomicU64,
+    emitter: Option<ProgressEmitter>,
+}
+
+impl JobManager {
+    /// Менеджер, задачи которого сообщают о прогрессе через `emitter`
+    pub fn with_emitter(emitter: ProgressEmitter) -> Self {
+        Self {
+            emitter: Some(emitter),
+            ..Self::default()
+        }
+    }
+
+    pub fn create(&self, kind: JobKind) -> Arc<Job> {
+        let sequence = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let kind_name = match kind {
+            JobKind::Scan => "scan",
+            JobKind::Clean => "clean",
+            JobKind::Analyze => "analyze",
+        };
+
+        let job = Arc::new(Job {
+            id: format!("{}-{}", kind_name, sequence),
+            kind,
+            sequence,
+            started_at: Utc::now(),
+            started: Instant::now(),
+            progress: AtomicU64::new(0f64.to_bits()),
+            cancelled: AtomicBool::new(false),
+            outcome: Mutex::new(JobOutcome {
+                state: JobState::Running,
+                result: None,
+                error: None,
+                finished_at: None,
+            }),
+            stage_index: AtomicUsize::new(0),
+            stage_count: AtomicUsize::new(0),
+            files_seen: AtomicU64::new(0),
+            files_matched: AtomicU64::new(0),
+            bytes_matched: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            last_event_ms: AtomicU64::new(0),
+            scan_progress: OnceLock::new(),
+            emitter: self.emitter.clone(),
+        });
+
+        let mut jobs = self.jobs.lock().unwrap();
+        Self::forget_old_jobs(&mut jobs);
+        jobs.insert(job.id.clone(), job.clone());
+        job
+    }
+
+    pub fn get(&self, id: &str) -> Result<Arc<Job>, CleanerError> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| CleanerError::JobNotFound { id: id.to_string() })
+    }
+
+    /// Последняя запущенная задача указанного типа
+    pub fn latest(&self, kind: JobKind) -> Option<Arc<Job>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|job| job.kind == kind)
+            .max_by_key(|job| job.sequence)
+            .cloned()
+    }
+
+    fn forget_old_jobs(jobs: &mut HashMap<String, Arc<Job>>) {
+        let mut finished: Vec<(u64, String)> = jobs
+            .values()
+            .filter(|job| job.is_finished())
+            .map(|job| (job.sequence, job.id.clone()))
+            .collect();
+
+        if finished.len() < FINISHED_JOBS_LIMIT {
+            return;
+        }
+
+        finished.sort();
+        for (_, id) in finished.iter().take(finished.len() + 1 - FINISHED_JOBS_LIMIT) {
+            jobs.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panicking_job_fails() {
+        let jobs = JobManager::default();
+        let job = jobs.create(JobKind::Scan);
+
+        job.run(|| panic!("walker exploded"));
+
+        let status = job.status();
+        assert_eq!(status.state, JobState::Failed);
+        assert!(status.error.unwrap().contains("walker exploded"));
+        assert!(status.finished_at.is_some());
+    }
+
+    #[test]
+    fn scan_progress_follows_directories() {
+        let jobs = JobManager::default();
+        let job = jobs.create(JobKind::Scan);
+        let dir = Path::new("/tmp");
+
+        job.expect_scan(HashMap::from([
+            ("cache".to_string(), ScanEstimate { expected_dirs: 300, roots: 1 }),
+            ("logs".to_string(), ScanEstimate { expected_dirs: 100, roots: 1 }),
+            // Без прошлого сканирования категория продвигается по корням и весит как средняя
+            ("new".to_string(), ScanEstimate { expected_dirs: 0, roots: 2 }),
+        ]));
+        assert_eq!(job.progress(), 0.0);
+
+        for _ in 0..150 {
+            job.dir_seen("cache", dir);
+        }
+        assert!((job.progress() - 150.0 / 600.0).abs() < 1e-9);
+
+        job.root_done("new");
+        assert!((job.progress() - 250.0 / 600.0).abs() < 1e-9);
+
+        // Каталогов больше, чем в прошлый раз: категория упирается в 99% до завершения
+        for _ in 0..200 {
+            job.dir_seen("logs", dir);
+        }
+        assert!((job.progress() - 349.0 / 600.0).abs() < 1e-9);
+
+        job.category_done("logs");
+        job.category_done("cache");
+        job.category_done("new");
+        assert_eq!(job.progress(), 1.0);
+    }
+
+    #[test]
+    fn completed_job_keeps_result() {
+        let jobs = JobManager::default();
+        let job = jobs.create(JobKind::Clean);
+
+        job.run(|| Ok(serde_json::json!({ "files_removed": 3 })));
+
+        assert_eq!(job.status().state, JobState::Completed);
+        assert_eq!(job.result().unwrap()["files_removed"], 3);
+    }
+}
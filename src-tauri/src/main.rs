@@ -1,47 +1,256 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod analyzer;
+mod browsers;
 mod cleaner;
 mod error;
 mod config;
+mod duplicates;
+mod jobs;
+mod mounts;
+mod ncdu;
+mod ownership;
+mod patterns;
+mod quarantine;
+mod safety;
+mod scan_index;
+mod shred;
+mod trash;
+mod walker;
 
+use analyzer::UsageOptions;
 use cleaner::CleanerService;
 use config::AppConfig;
-use tauri::{State, Window};
+use jobs::{JobKind, JobManager, JobStatus, ProgressEvent};
+use mounts::{MountTable, MountUsage};
+use tauri::{Manager, State, Window};
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 type CleanerState = Arc<Mutex<CleanerService>>;
+type JobsState = Arc<JobManager>;
 
+/// Запускает сканирование в фоне и сразу возвращает идентификатор задачи.
+/// `full_rescan` заставляет заново прочитать все каталоги, не доверяя индексу прошлого сканирования
 #[tauri::command]
-async fn scan_system(cleaner: State<'_, CleanerState>) -> Result<serde_json::Value, String> {
-    let mut service = cleaner.lock().await;
-    service.scan_system()
-        .await
-        .map_err(|e| e.to_string())
+async fn scan_system(
+    cleaner: State<'_, CleanerState>,
+    jobs: State<'_, JobsState>,
+    full_rescan: Option<bool>,
+) -> Result<String, String> {
+    let job = jobs.create(JobKind::Scan);
+    let job_id = job.id.clone();
+    let cleaner = cleaner.inner().clone();
+
+    // Сервис заблокирован только на время снимка и записи результатов, а не на всё сканирование
+    tauri::async_runtime::spawn_blocking(move || {
+        job.run(|| {
+            let mut snapshot = cleaner.blocking_lock().snapshot(&[]);
+            let result = snapshot.scan_system(&job, full_rescan.unwrap_or(false))?;
+            cleaner.blocking_lock().store_scan(snapshot);
+            Ok(result)
+        });
+    });
+
+    Ok(job_id)
 }
 
+/// Запускает очистку в фоне и сразу возвращает идентификатор задачи
 #[tauri::command]
 async fn clean_system(
     cleaner: State<'_, CleanerState>,
+    jobs: State<'_, JobsState>,
     categories: Vec<String>,
+    dry_run: Option<bool>,
+) -> Result<String, String> {
+    let job = jobs.create(JobKind::Clean);
+    let job_id = job.id.clone();
+    let cleaner = cleaner.inner().clone();
+
+    // Очистка идёт по копии планов; в общем сервисе планы обновляются, когда она закончится
+    tauri::async_runtime::spawn_blocking(move || {
+        job.run(|| {
+            let dry_run = dry_run.unwrap_or(false);
+            let mut snapshot = cleaner.blocking_lock().snapshot(&categories);
+            let result = snapshot.clean_categories(categories.clone(), dry_run, &job);
+            if !dry_run {
+                cleaner.blocking_lock().forget_removed(&categories);
+            }
+            result
+        });
+    });
+
+    Ok(job_id)
+}
+
+/// Запускает анализ занятого места в каталоге `path` и сразу возвращает идентификатор задачи.
+/// Результат задачи — дерево размеров глубиной `max_depth` с `max_children` самыми большими потомками на уровне
+#[tauri::command]
+async fn analyze_disk_usage(
+    cleaner: State<'_, CleanerState>,
+    jobs: State<'_, JobsState>,
+    path: String,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+    one_file_system: Option<bool>,
+) -> Result<String, String> {
+    let options = usage_options(max_depth, max_children, one_file_system);
+
+    let job = jobs.create(JobKind::Analyze);
+    let job_id = job.id.clone();
+    let cleaner = cleaner.inner().clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        job.run(|| {
+            let snapshot = cleaner.blocking_lock().snapshot(&[]);
+            snapshot.analyze_usage(Path::new(&path), &options, &job)
+        });
+    });
+
+    Ok(job_id)
+}
+
+/// Сохраняет результат задачи сканирования или анализа в формате ncdu, чтобы открыть его через `ncdu -f`.
+/// Для сканирования `categories` ограничивает выгрузку выбранными категориями
+#[tauri::command]
+async fn export_ncdu(
+    jobs: State<'_, JobsState>,
+    job_id: String,
+    output: String,
+    categories: Option<Vec<String>>,
+) -> Result<(), String> {
+    let job = jobs.get(&job_id).map_err(|e| e.to_string())?;
+    let result = job.result().ok_or_else(|| format!("Job {} has no result", job_id))?;
+
+    // Выгрузка большого дерева пишется долго, поэтому не на потоке обработчика команд
+    tauri::async_runtime::spawn_blocking(move || {
+        ncdu::export_job_result(job.kind, result, categories.as_deref(), Path::new(&output))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Загружает выгрузку ncdu и возвращает её в том же виде, что и анализ диска
+#[tauri::command]
+async fn import_ncdu(
+    path: String,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+) -> Result<serde_json::Value, String> {
+    let options = usage_options(max_depth, max_children, None);
+
+    // Разбор выгрузки на миллионы элементов тоже занимает секунды
+    tauri::async_runtime::spawn_blocking(move || {
+        ncdu::import(Path::new(&path), &options).and_then(|report| Ok(serde_json::to_value(report)?))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+fn usage_options(max_depth: Option<usize>, max_children: Option<usize>, one_file_system: Option<bool>) -> UsageOptions {
+    let defaults = UsageOptions::default();
+    UsageOptions {
+        max_depth: max_depth.unwrap_or(defaults.max_depth),
+        max_children: max_children.unwrap_or(defaults.max_children),
+        one_file_system: one_file_system.unwrap_or(defaults.one_file_system),
+    }
+}
+
+/// Итоги последнего сканирования по выбранным категориям; жёсткие ссылки из разных категорий считаются один раз
+#[tauri::command]
+async fn get_scan_totals(
+    cleaner: State<'_, CleanerState>,
+    categories: Option<Vec<String>>,
 ) -> Result<serde_json::Value, String> {
-    let mut service = cleaner.lock().await;
-    service.clean_categories(categories)
+    let service = cleaner.lock().await;
+    service.scan_totals(categories.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Смонтированные файловые системы с общим, занятым и свободным местом
+#[tauri::command]
+async fn get_mounts() -> Result<Vec<MountUsage>, String> {
+    // statvfs на зависшем сетевом разделе блокируется надолго, поэтому не на потоке обработчика команд
+    tauri::async_runtime::spawn_blocking(|| MountTable::load().usage())
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn get_scan_progress(cleaner: State<'_, CleanerState>) -> Result<f64, String> {
+async fn get_job_status(jobs: State<'_, JobsState>, job_id: String) -> Result<JobStatus, String> {
+    jobs.get(&job_id)
+        .map(|job| job.status())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn cancel_job(jobs: State<'_, JobsState>, job_id: String) -> Result<(), String> {
+    jobs.get(&job_id)
+        .map(|job| job.cancel())
+        .map_err(|e| e.to_string())
+}
+
+/// Результат завершённой задачи. Для отменённой очистки возвращается то, что успело выполниться
+#[tauri::command]
+async fn get_job_result(jobs: State<'_, JobsState>, job_id: String) -> Result<Option<serde_json::Value>, String> {
+    jobs.get(&job_id)
+        .map(|job| job.result())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_scan_progress(jobs: State<'_, JobsState>) -> Result<f64, String> {
+    Ok(jobs.latest(JobKind::Scan).map_or(0.0, |job| job.progress()))
+}
+
+#[tauri::command]
+async fn get_clean_progress(jobs: State<'_, JobsState>) -> Result<f64, String> {
+    Ok(jobs.latest(JobKind::Clean).map_or(0.0, |job| job.progress()))
+}
+
+#[tauri::command]
+async fn list_quarantine_sessions(cleaner: State<'_, CleanerState>) -> Result<serde_json::Value, String> {
+    let service = cleaner.lock().await;
+    service.list_quarantine_sessions()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_quarantine_session(
+    cleaner: State<'_, CleanerState>,
+    session_id: String,
+) -> Result<serde_json::Value, String> {
+    let service = cleaner.lock().await;
+    service.restore_quarantine(&session_id, &[])
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_quarantine_files(
+    cleaner: State<'_, CleanerState>,
+    session_id: String,
+    paths: Vec<String>,
+) -> Result<serde_json::Value, String> {
+    if paths.is_empty() {
+        return Err("Не выбраны файлы для восстановления".to_string());
+    }
     let service = cleaner.lock().await;
-    Ok(service.get_scan_progress())
+    service.restore_quarantine(&session_id, &paths)
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn get_clean_progress(cleaner: State<'_, CleanerState>) -> Result<f64, String> {
+async fn purge_quarantine(
+    cleaner: State<'_, CleanerState>,
+    retention_days: Option<u32>,
+) -> Result<serde_json::Value, String> {
     let service = cleaner.lock().await;
-    Ok(service.get_clean_progress())
+    service.purge_quarantine(retention_days)
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -71,11 +280,32 @@ fn main() {
 
     tauri::Builder::default()
         .manage(cleaner_service)
+        .setup(|app| {
+            // Задачи сообщают о прогрессе событием `job-progress`, частота ограничена в самих задачах
+            let handle = app.handle();
+            let jobs = JobManager::with_emitter(Arc::new(move |event: &ProgressEvent| {
+                let _ = handle.emit_all("job-progress", event);
+            }));
+            app.manage(JobsState::new(jobs));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_system,
             clean_system,
+            analyze_disk_usage,
+            export_ncdu,
+            import_ncdu,
+            get_scan_totals,
+            get_mounts,
+            get_job_status,
+            cancel_job,
+            get_job_result,
             get_scan_progress,
             get_clean_progress,
+            list_quarantine_sessions,
+            restore_quarantine_session,
+            restore_quarantine_files,
+            purge_quarantine,
             minimize_window,
             maximize_window,
             close_window
@@ -0,0 +1,262 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Точка монтирования из таблицы ядра
+#[derive(Debug, Clone)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub options: Vec<String>,
+}
+
+impl MountEntry {
+    pub fn has_option(&self, option: &str) -> bool {
+        self.options.iter().any(|o| o == option)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MountTable {
+    entries: Vec<MountEntry>,
+}
+
+impl MountTable {
+    /// Читает таблицу монтирования. Вне Linux таблица пустая
+    pub fn load() -> Self {
+        let content = match std::fs::read_to_string("/proc/self/mounts") {
+            Ok(c) => c,
+            Err(_) => return Self::default(),
+        };
+
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let device = unescape(fields.next()?);
+                let mount_point = PathBuf::from(unescape(fields.next()?));
+                let fs_type = fields.next()?.to_string();
+                let options = fields.next()?.split(',').map(str::to_string).collect();
+                Some(MountEntry {
+                    device,
+                    mount_point,
+                    fs_type,
+                    options,
+                })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[MountEntry] {
+        &self.entries
+    }
+
+    /// Находит файловую систему, которой принадлежит путь (самая длинная подходящая точка монтирования)
+    pub fn find(&self, path: &Path) -> Option<&MountEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| path.starts_with(&entry.mount_point))
+            .max_by_key(|entry| entry.mount_point.components().count())
+    }
+
+    /// Файловые системы, смонтированные внутри каталога `dir` на любой глубине
+    pub fn nested<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = &'a MountEntry> + 'a {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.mount_point != dir && entry.mount_point.starts_with(dir))
+    }
+}
+
+/// Заполненность смонтированной файловой системы
+#[derive(Debug, Clone, Serialize)]
+pub struct MountUsage {
+    pub mount_point: String,
+    pub device: String,
+    pub fs_type: String,
+    pub total: u64,
+    pub used: u64,
+    /// Место, доступное обычному пользователю (без резерва root)
+    pub free: u64,
+    pub read_only: bool,
+    pub removable: bool,
+}
+
+impl MountTable {
+    /// Файловые системы с их размерами. Псевдофайловые системы без блоков (proc, sysfs, cgroup) не попадают в список
+    pub fn usage(&self) -> Vec<MountUsage> {
+        // Точку монтирования могли смонтировать поверх: видна последняя запись
+        let mut visible: HashMap<&Path, &MountEntry> = HashMap::new();
+        for entry in &self.entries {
+            visible.insert(&entry.mount_point, entry);
+        }
+
+        let mut usage: Vec<MountUsage> = visible
+            .into_values()
+            // squashfs — это образы snap-пакетов: они всегда заполнены и только для чтения
+            .filter(|entry| entry.fs_type != "squashfs" && !is_system_mount(&entry.mount_point))
+            .filter_map(|entry| {
+                let stats = fs_stats(&entry.mount_point)?;
+                if stats.total == 0 {
+                    return None;
+                }
+                Some(MountUsage {
+                    mount_point: entry.mount_point.to_string_lossy().to_string(),
+                    device: entry.device.clone(),
+                    fs_type: entry.fs_type.clone(),
+                    total: stats.total,
+                    used: stats.used,
+                    free: stats.free,
+                    read_only: stats.read_only || entry.has_option("ro"),
+                    removable: is_removable(&entry.device),
+                })
+            })
+            .collect();
+
+        usage.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+        usage
+    }
+}
+
+/// Служебные tmpfs ядра и systemd. Съёмные диски монтируются в /run/media, их оставляем
+fn is_system_mount(mount_point: &Path) -> bool {
+    ["/dev", "/proc", "/sys"].iter().any(|prefix| mount_point.starts_with(prefix))
+        || (mount_point.starts_with("/run") && !mount_point.starts_with("/run/media"))
+}
+
+struct FsStats {
+    total: u64,
+    used: u64,
+    free: u64,
+    read_only: bool,
+}
+
+// Типы полей statvfs зависят от платформы, на 64-битных преобразования в u64 пустые
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)]
+fn fs_stats(path: &Path) -> Option<FsStats> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: путь — корректная C-строка, структура принадлежит нам
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+
+    let block = u64::from(stats.f_frsize);
+    Some(FsStats {
+        total: u64::from(stats.f_blocks) * block,
+        used: u64::from(stats.f_blocks).saturating_sub(u64::from(stats.f_bfree)) * block,
+        free: u64::from(stats.f_bavail) * block,
+        read_only: stats.f_flag & libc::ST_RDONLY != 0,
+    })
+}
+
+#[cfg(not(unix))]
+fn fs_stats(_path: &Path) -> Option<FsStats> {
+    None
+}
+
+/// Съёмный ли накопитель: флаг `removable` блочного устройства (у раздела — у его диска)
+/// или подключение через USB
+fn is_removable(device: &str) -> bool {
+    let sys_path = match block_device(device) {
+        Some(path) => path,
+        None => return false,
+    };
+
+    if sys_path.components().any(|component| component.as_os_str().to_string_lossy().starts_with("usb")) {
+        return true;
+    }
+
+    std::fs::read_to_string(disk_of(&sys_path).join("removable")).is_ok_and(|flag| flag.trim() == "1")
+}
+
+/// Лежит ли файловая система на твердотельном накопителе (ядро считает диск невращающимся)
+pub fn is_solid_state(device: &str) -> bool {
+    block_device(device)
+        .and_then(|sys_path| std::fs::read_to_string(disk_of(&sys_path).join("queue/rotational")).ok())
+        .is_some_and(|flag| flag.trim() == "0")
+}
+
+/// Каталог блочного устройства в sysfs, например `/sys/devices/.../sda/sda1`
+fn block_device(device: &str) -> Option<PathBuf> {
+    let path = Path::new(device).canonicalize().ok().filter(|path| path.starts_with("/dev"))?;
+    Path::new("/sys/class/block").join(path.file_name()?).canonicalize().ok()
+}
+
+/// У раздела свойства накопителя лежат в каталоге его диска
+fn disk_of(sys_path: &Path) -> &Path {
+    if sys_path.join("partition").exists() {
+        sys_path.parent().unwrap_or(sys_path)
+    } else {
+        sys_path
+    }
+}
+
+/// В /proc/self/mounts пробелы и спецсимволы записаны восьмеричными escape-последовательностями (`\040`)
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let is_escape = bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b));
+
+        if is_escape {
+            let code = bytes[i + 1..i + 4]
+                .iter()
+                .fold(0u32, |acc, b| acc * 8 + (b - b'0') as u32);
+            result.push(code as u8);
+            i += 4;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&result).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_decodes_octal_escapes() {
+        assert_eq!(unescape("/media/usb\\040stick"), "/media/usb stick");
+        assert_eq!(unescape("tab\\011and\\134slash"), "tab\tand\\slash");
+        // Неполная или не восьмеричная последовательность остаётся как есть
+        assert_eq!(unescape("/mnt/a\\08"), "/mnt/a\\08");
+        assert_eq!(unescape("/mnt/end\\04"), "/mnt/end\\04");
+        assert_eq!(unescape("/plain"), "/plain");
+    }
+
+    #[test]
+    fn find_picks_the_deepest_mount() {
+        let entry = |mount_point: &str| MountEntry {
+            device: "dev".to_string(),
+            mount_point: PathBuf::from(mount_point),
+            fs_type: "ext4".to_string(),
+            options: vec![],
+        };
+        let table = MountTable {
+            entries: vec![entry("/"), entry("/home"), entry("/home/user/data")],
+        };
+
+        let find = |path: &str| table.find(Path::new(path)).map(|mount| mount.mount_point.clone());
+        assert_eq!(find("/home/user/file"), Some(PathBuf::from("/home")));
+        assert_eq!(find("/home/user/data/x"), Some(PathBuf::from("/home/user/data")));
+        // Сравнение по компонентам: /homework не лежит в /home
+        assert_eq!(find("/homework"), Some(PathBuf::from("/")));
+
+        let nested: Vec<&Path> = table.nested(Path::new("/home")).map(|mount| mount.mount_point.as_path()).collect();
+        assert_eq!(nested, [Path::new("/home/user/data")]);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::analyzer::{self, Item, UsageNode, UsageOptions, UsageReport};
+use crate::cleaner::{FileId, FileInfo, ScanResult};
+use crate::error::CleanerError;
+use crate::jobs::JobKind;
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::io::BufWriter;
+use std::path::{Component, Path, PathBuf};
+
+/// Версия формата выгрузки ncdu: 1.2 читают и ncdu 1.x, и ncdu 2.x
+const MAJOR_VERSION: u64 = 1;
+const MINOR_VERSION: u64 = 2;
+
+/// Предельная вложенность выгрузки при загрузке. Разбор рекурсивный, и без предела
+/// подложенная выгрузка переполнила бы стек
+const MAX_DEPTH: usize = 1024;
+
+/// Сохраняет результат задачи в формате `ncdu -o`: сканирование — как дерево найденных файлов,
+/// анализ — как дерево размеров. Для сканирования можно выбрать категории
+pub fn export_job_result(
+    kind: JobKind,
+    result: Value,
+    categories: Option<&[String]>,
+    output: &Path,
+) -> Result<(), CleanerError> {
+    let root = match kind {
+        JobKind::Analyze => usage_entry(&serde_json::from_value::<UsageReport>(result)?.root, true),
+        JobKind::Scan => {
+            let results: HashMap<String, ScanResult> = serde_json::from_value(result)?;
+            let files = results
+                .values()
+                .filter(|result| match categories {
+                    Some(names) => names.contains(&result.category),
+                    None => true,
+                })
+                .flat_map(|result| &result.files);
+            scan_entry(files)
+        }
+        JobKind::Clean => {
+            return Err(CleanerError::NothingToExport {
+                reason: "clean results have no file tree".to_string(),
+            })
+        }
+    };
+
+    let dump = json!([
+        MAJOR_VERSION,
+        MINOR_VERSION,
+        {
+            "progname": env!("CARGO_PKG_NAME"),
+            "progver": env!("CARGO_PKG_VERSION"),
+            "timestamp": Utc::now().timestamp(),
+        },
+        root,
+    ]);
+
+    let file = std::fs::File::create(output)?;
+    serde_json::to_writer(BufWriter::new(file), &dump)?;
+    Ok(())
+}
+
+/// Загружает выгрузку ncdu в анализатор. Дерево обрезается так же, как при анализе диска
+pub fn import(input: &Path, options: &UsageOptions) -> Result<UsageReport, CleanerError> {
+    let invalid = |reason: &str| CleanerError::InvalidDump {
+        reason: reason.to_string(),
+    };
+
+    // Встроенный предел serde_json — 128 уровней, а каждый каталог добавляет уровень. Поэтому он снят,
+    // а глубина проверяется до разбора, пока рекурсия ещё не началась
+    let text = std::fs::read_to_string(input)?;
+    if nesting_depth(&text) > MAX_DEPTH {
+        return Err(invalid(&format!("directories nested deeper than {} levels", MAX_DEPTH)));
+    }
+    let mut deserializer = serde_json::Deserializer::from_str(&text);
+    deserializer.disable_recursion_limit();
+    let dump = Value::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    let parts = dump.as_array().ok_or_else(|| invalid("expected an array"))?;
+    if parts.first().and_then(Value::as_u64) != Some(MAJOR_VERSION) {
+        return Err(invalid("unsupported format version"));
+    }
+    let root = parts.get(3).ok_or_else(|| invalid("missing root directory"))?;
+
+    let mut items = Vec::new();
+    read_entry(root, None, 0, &mut items).map_err(|reason| invalid(&reason))?;
+
+    let mut report = analyzer::build_report(items, options).ok_or_else(|| invalid("empty tree"))?;
+    let timestamp = parts.get(2).and_then(|meta| meta.get("timestamp")).and_then(Value::as_i64);
+    if let Some(timestamp) = timestamp.and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single()) {
+        report.analyzed_at = timestamp;
+    }
+    Ok(report)
+}
+
+/// Узел анализа в формате ncdu. ncdu сам суммирует содержимое каталога, поэтому у каталога
+/// записывается только собственный размер, а скрытые потомки — одним условным элементом
+fn usage_entry(node: &UsageNode, is_root: bool) -> Value {
+    let name = if is_root { &node.path } else { &node.name };
+
+    if !node.is_directory {
+        return json!({ "name": name, "asize": node.apparent_size, "dsize": node.size });
+    }
+
+    let children_size: u64 = node.children.iter().map(|child| child.size).sum::<u64>() + node.hidden_size;
+    let children_apparent: u64 = node.children.iter().map(|child| child.apparent_size).sum();
+
+    let mut entries = vec![json!({
+        "name": name,
+        "asize": node.apparent_size.saturating_sub(children_apparent),
+        "dsize": node.size.saturating_sub(children_size),
+    })];
+    entries.extend(node.children.iter().map(|child| usage_entry(child, false)));
+
+    if node.hidden_children > 0 {
+        entries.push(json!({
+            "name": format!("[ещё {} элементов]", node.hidden_children),
+            "dsize": node.hidden_size,
+            "notreg": true,
+        }));
+    }
+
+    Value::Array(entries)
+}
+
+/// Каталог дерева найденных файлов
+#[derive(Default)]
+struct ScanDir<'a> {
+    dirs: BTreeMap<OsString, ScanDir<'a>>,
+    files: Vec<&'a FileInfo>,
+}
+
+/// Находки сканирования в формате ncdu. Корень — общий родитель всех найденных файлов
+fn scan_entry<'a>(files: impl Iterator<Item = &'a FileInfo>) -> Value {
+    let files: Vec<&FileInfo> = files.collect();
+    let root = common_parent(files.iter().map(|file| Path::new(&file.path)));
+
+    let mut tree = ScanDir::default();
+    for file in files {
+        let relative = Path::new(&file.path).strip_prefix(&root).unwrap_or(Path::new(&file.path));
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        let dir = parent.iter().fold(&mut tree, |dir, name| dir.dirs.entry(name.to_os_string()).or_default());
+        dir.files.push(file);
+    }
+
+    scan_dir_entry(&root.to_string_lossy(), &tree)
+}
+
+fn scan_dir_entry(name: &str, dir: &ScanDir) -> Value {
+    let mut entries = vec![json!({ "name": name })];
+
+    entries.extend(dir.dirs.iter().map(|(name, dir)| scan_dir_entry(&name.to_string_lossy(), dir)));
+    entries.extend(dir.files.iter().map(|file| {
+        let name = Path::new(&file.path)
+            .file_name()
+            .map_or_else(|| file.path.clone(), |name| name.to_string_lossy().to_string());
+        let mut info = json!({
+            "name": name,
+            "asize": file.size,
+            "dsize": file.allocated,
+            "mtime": file.modified,
+        });
+
+        // Каталог, удаляемый целиком, содержимого в результатах не имеет: пишем его пустым с полным размером
+        if file.is_directory {
+            return json!([info]);
+        }
+
+        if let Some(id) = file.file_id {
+            info["dev"] = json!(id.device);
+            info["ino"] = json!(id.inode);
+            if file.links > 1 {
+                info["hlnkc"] = json!(true);
+                info["nlink"] = json!(file.links);
+            }
+        }
+        info
+    }));
+
+    Value::Array(entries)
+}
+
+/// Самый глубокий каталог, содержащий все пути
+fn common_parent<'a>(mut paths: impl Iterator<Item = &'a Path>) -> PathBuf {
+    let first = match paths.next() {
+        Some(path) => path.parent().unwrap_or(path).to_path_buf(),
+        None => return PathBuf::from("/"),
+    };
+
+    let common = paths.fold(first, |common, path| {
+        common
+            .components()
+            .zip(path.components())
+            .take_while(|(a, b)| a == b)
+            .map(|(component, _)| component)
+            .collect()
+    });
+
+    if common.as_os_str().is_empty() {
+        PathBuf::from(Component::RootDir.as_os_str())
+    } else {
+        common
+    }
+}
+
+/// Наибольшая вложенность массивов и объектов в тексте JSON. Считается без рекурсии
+fn nesting_depth(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut deepest = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in text.bytes() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    deepest
+}
+
+/// Разбирает элемент выгрузки: массив — каталог с содержимым, объект — файл.
+/// Устройство наследуется от родителя, если у элемента оно не указано
+fn read_entry(entry: &Value, parent: Option<&Path>, parent_device: u64, items: &mut Vec<Item>) -> Result<(), String> {
+    let (info, children) = match entry {
+        Value::Array(entries) => match entries.split_first() {
+            Some((info, children)) => (info, Some(children)),
+            None => return Err("directory without info".to_string()),
+        },
+        info => (info, None),
+    };
+
+    let info: &Map<String, Value> = info.as_object().ok_or("expected an item info object")?;
+    let name = info.get("name").and_then(Value::as_str).ok_or("item without name")?;
+    let path = parent.map_or_else(|| PathBuf::from(name), |parent| parent.join(name));
+    let number = |key: &str| info.get(key).and_then(Value::as_u64);
+    let device = number("dev").unwrap_or(parent_device);
+
+    // Исключённые элементы ncdu не сканировал; другие файловые системы показываем как пропущенные разделы
+    if let Some(excluded) = info.get("excluded").and_then(Value::as_str) {
+        if excluded == "otherfs" || excluded == "othfs" {
+            items.push(Item::Mount(path));
+        }
+        return Ok(());
+    }
+
+    let allocated = number("dsize").unwrap_or(0);
+    let apparent = number("asize").unwrap_or(0);
+
+    match children {
+        Some(children) => {
+            items.push(Item::Dir {
+                path: path.clone(),
+                allocated,
+                apparent,
+            });
+            for child in children {
+                read_entry(child, Some(&path), device, items)?;
+            }
+        }
+        None => {
+            let hardlinked = info.get("hlnkc").and_then(Value::as_bool).unwrap_or(false);
+            items.push(Item::File {
+                path,
+                allocated,
+                apparent,
+                id: number("ino").map(|inode| FileId { device, inode }),
+                links: number("nlink").unwrap_or(if hardlinked { 2 } else { 1 }),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Результат сканирования с файлом в корне и файлом на глубине `depth` каталогов
+    fn deep_scan(depth: usize) -> Value {
+        let deep: PathBuf = std::iter::once("/".to_string())
+            .chain((0..depth).map(|level| format!("d{}", level)))
+            .chain(std::iter::once("deep.tmp".to_string()))
+            .collect();
+        let file = |path: &Path| json!({
+            "path": path.to_string_lossy(),
+            "size": 100,
+            "allocated": 4096,
+            "file_id": null,
+            "modified": 0,
+            "is_directory": false,
+        });
+
+        json!({
+            "fixture": {
+                "category": "fixture",
+                "total_files": 2,
+                "total_size": 8192,
+                "files": [file(Path::new("/top.tmp")), file(&deep)],
+                "errors": [],
+                "skipped_by_size": 0,
+                "skipped_by_size_bytes": 0,
+                "overlaps": [],
+                "cut_off": [],
+                "duplicates": [],
+                "mount": null,
+            }
+        })
+    }
+
+    fn round_trip(depth: usize, name: &str) -> Result<UsageReport, CleanerError> {
+        let output = std::env::temp_dir().join(format!("ncdu-{}-{}.json", name, std::process::id()));
+        export_job_result(JobKind::Scan, deep_scan(depth), None, &output).unwrap();
+        let options = UsageOptions {
+            max_depth: usize::MAX,
+            max_children: usize::MAX,
+            one_file_system: true,
+        };
+        let report = import(&output, &options);
+        std::fs::remove_file(&output).unwrap();
+        report
+    }
+
+    #[test]
+    fn deep_trees_survive_export_and_import() {
+        let report = round_trip(300, "deep").unwrap();
+        assert_eq!(report.root.files, 2);
+        assert_eq!(report.root.size, 8192);
+
+        let mut node = &report.root;
+        let mut levels = 0;
+        while let Some(dir) = node.children.iter().find(|child| child.is_directory) {
+            node = dir;
+            levels += 1;
+        }
+        assert_eq!(levels, 300);
+        assert_eq!(node.children[0].name, "deep.tmp");
+    }
+
+    #[test]
+    fn too_deep_dump_is_rejected_before_parsing() {
+        let error = round_trip(MAX_DEPTH + 1, "too-deep").unwrap_err();
+        assert!(matches!(error, CleanerError::InvalidDump { .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn nesting_ignores_brackets_in_names() {
+        assert_eq!(nesting_depth(r#"[1, {"name": "[[[\"]]"}]"#), 2);
+        assert_eq!(nesting_depth("[]"), 1);
+        assert_eq!(nesting_depth("0"), 0);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::cleaner::{CategoryOverlap, ScanResult};
+use crate::config::CleanupCategory;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Находка одной категории: индекс результата и индекс файла в нём
+struct Claim {
+    path: PathBuf,
+    result: usize,
+    file: usize,
+    is_directory: bool,
+}
+
+/// Оставляет каждый файл только в одной категории-владельце и записывает пересечения в отчёты.
+///
+/// Владельца выбирают по явному `priority`, затем по глубине корня категории, который содержит файл,
+/// и при полном равенстве — по имени категории. Каталог, удаляемый целиком, и всё найденное
+/// внутри него другими категориями считается одной находкой: она целиком достаётся владельцу.
+pub fn resolve_overlaps(results: &mut [ScanResult], categories: &HashMap<String, CleanupCategory>) {
+    let mut claims: Vec<Claim> = results
+        .iter()
+        .enumerate()
+        .flat_map(|(result, scan)| {
+            scan.files.iter().enumerate().map(move |(file, info)| Claim {
+                path: PathBuf::from(&info.path),
+                result,
+                file,
+                is_directory: info.is_directory,
+            })
+        })
+        .collect();
+
+    // Сравнение путей покомпонентное, поэтому содержимое каталога идёт сразу за ним
+    claims.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let rank = |claim: &Claim| {
+        let name = results[claim.result].category.as_str();
+        let category = categories.get(name);
+        let priority = category.map_or(0, |category| category.priority);
+        let depth = category.map_or(0, |category| root_depth(&category.paths, &claim.path));
+        (priority, depth, Reverse(name))
+    };
+
+    // (результат, файл, результат-владелец)
+    let mut dropped = Vec::new();
+    let mut start = 0;
+
+    while start < claims.len() {
+        let anchor = &claims[start];
+        let end = start
+            + claims[start..]
+                .iter()
+                .take_while(|claim| {
+                    claim.path == anchor.path || (anchor.is_directory && claim.path.starts_with(&anchor.path))
+                })
+                .count();
+
+        let group = &claims[start..end];
+        if group.len() > 1 {
+            let owner = group.iter().max_by_key(|claim| rank(claim)).map_or(anchor.result, |claim| claim.result);
+            let mut kept = HashSet::new();
+
+            for claim in group {
+                // Один и тот же путь мог попасть в категорию дважды через вложенные корни
+                let duplicate = claim.result == owner && !kept.insert(&claim.path);
+                if claim.result != owner || duplicate {
+                    dropped.push((claim.result, claim.file, owner));
+                }
+            }
+        }
+
+        start = end;
+    }
+
+    if dropped.is_empty() {
+        return;
+    }
+
+    let mut removed: Vec<HashSet<usize>> = vec![HashSet::new(); results.len()];
+    let mut overlaps: HashMap<(usize, usize), (usize, u64)> = HashMap::new();
+
+    for (result, file, owner) in dropped {
+        removed[result].insert(file);
+        if result != owner {
+            let overlap = overlaps.entry((result, owner)).or_default();
+            overlap.0 += 1;
+            overlap.1 += results[result].files[file].allocated;
+        }
+    }
+
+    for ((result, owner), (files, size)) in overlaps {
+        let owner = results[owner].category.clone();
+        results[result].overlaps.push(CategoryOverlap { owner, files, size });
+    }
+
+    for (scan, removed) in results.iter_mut().zip(removed) {
+        if !removed.is_empty() {
+            let mut index = 0;
+            scan.files.retain(|_| {
+                index += 1;
+                !removed.contains(&(index - 1))
+            });
+            scan.recount();
+        }
+        scan.overlaps.sort_by(|a, b| a.owner.cmp(&b.owner));
+    }
+}
+
+/// Глубина самого вложенного корня категории, который содержит путь
+fn root_depth(roots: &[PathBuf], path: &Path) -> usize {
+    roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .map(|root| root.components().count())
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaner::FileInfo;
+
+    fn file(path: &str, is_directory: bool) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size: 100,
+            allocated: 4096,
+            file_id: None,
+            links: 1,
+            modified: 0,
+            is_directory,
+        }
+    }
+
+    fn result(category: &str, files: Vec<FileInfo>) -> ScanResult {
+        let mut result = ScanResult {
+            category: category.to_string(),
+            total_files: 0,
+            total_size: 0,
+            files,
+            errors: Vec::new(),
+            skipped_by_size: 0,
+            skipped_by_size_bytes: 0,
+            overlaps: Vec::new(),
+            cut_off: Vec::new(),
+            duplicates: Vec::new(),
+            mount: None,
+            scanned_at: None,
+        };
+        result.recount();
+        result
+    }
+
+    fn category(root: &str, priority: i32) -> CleanupCategory {
+        CleanupCategory {
+            paths: vec![PathBuf::from(root)],
+            priority,
+            ..Default::default()
+        }
+    }
+
+    fn paths(result: &ScanResult) -> Vec<&str> {
+        result.files.iter().map(|file| file.path.as_str()).collect()
+    }
+
+    #[test]
+    fn deeper_root_owns_shared_files() {
+        let categories = HashMap::from([
+            ("cache".to_string(), category("/home/user/.cache", 0)),
+            ("thumbnails".to_string(), category("/home/user/.cache/thumbnails", 0)),
+        ]);
+        let mut results = [
+            result("cache", vec![file("/home/user/.cache/a", false), file("/home/user/.cache/thumbnails/t.png", false)]),
+            result("thumbnails", vec![file("/home/user/.cache/thumbnails/t.png", false)]),
+        ];
+
+        resolve_overlaps(&mut results, &categories);
+
+        assert_eq!(paths(&results[0]), ["/home/user/.cache/a"]);
+        assert_eq!(results[0].total_files, 1);
+        assert_eq!(results[0].total_size, 4096);
+        assert_eq!(paths(&results[1]), ["/home/user/.cache/thumbnails/t.png"]);
+        assert_eq!(results[0].overlaps.len(), 1);
+        assert_eq!(results[0].overlaps[0].owner, "thumbnails");
+        assert_eq!(results[0].overlaps[0].files, 1);
+        assert_eq!(results[0].overlaps[0].size, 4096);
+        assert!(results[1].overlaps.is_empty());
+    }
+
+    #[test]
+    fn priority_beats_depth_and_name_breaks_ties() {
+        let categories = HashMap::from([
+            ("downloads".to_string(), category("/home/user/Downloads", 0)),
+            ("installers".to_string(), category("/home", 1)),
+            ("zeta".to_string(), category("/data", 0)),
+            ("alpha".to_string(), category("/data", 0)),
+        ]);
+        let mut results = [
+            result("downloads", vec![file("/home/user/Downloads/setup.exe", false)]),
+            result("installers", vec![file("/home/user/Downloads/setup.exe", false)]),
+            result("zeta", vec![file("/data/x", false)]),
+            result("alpha", vec![file("/data/x", false)]),
+        ];
+
+        resolve_overlaps(&mut results, &categories);
+
+        assert!(results[0].files.is_empty());
+        assert_eq!(paths(&results[1]), ["/home/user/Downloads/setup.exe"]);
+        // При полном равенстве владельцем становится категория с меньшим именем
+        assert!(results[2].files.is_empty());
+        assert_eq!(paths(&results[3]), ["/data/x"]);
+    }
+
+    #[test]
+    fn directory_unit_takes_everything_inside() {
+        let categories = HashMap::from([
+            ("dev".to_string(), category("/src", 1)),
+            ("temp".to_string(), category("/src", 0)),
+        ]);
+        let mut results = [
+            result("dev", vec![file("/src/project/node_modules", true)]),
+            result("temp", vec![
+                file("/src/project/node_modules/a.tmp", false),
+                file("/src/project/node_modules/deep/b.tmp", false),
+                file("/src/project/node_modules_other.tmp", false),
+            ]),
+        ];
+
+        resolve_overlaps(&mut results, &categories);
+
+        assert_eq!(paths(&results[0]), ["/src/project/node_modules"]);
+        assert_eq!(paths(&results[1]), ["/src/project/node_modules_other.tmp"]);
+        assert_eq!(results[1].overlaps[0].files, 2);
+    }
+
+    #[test]
+    fn same_path_twice_in_one_category_is_kept_once() {
+        let categories = HashMap::from([("logs".to_string(), category("/var/log", 0))]);
+        let mut results = [result("logs", vec![file("/var/log/a.log", false), file("/var/log/a.log", false)])];
+
+        resolve_overlaps(&mut results, &categories);
+
+        assert_eq!(paths(&results[0]), ["/var/log/a.log"]);
+        assert!(results[0].overlaps.is_empty());
+    }
+}
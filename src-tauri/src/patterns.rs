@@ -0,0 +1,198 @@
+use crate::error::CleanerError;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Скомпилированный набор шаблонов категории.
+///
+/// Шаблон без `/` сравнивается с именем файла на любой глубине, шаблон с `/` — с путём
+/// относительно корня категории. Шаблоны с `!` в начале исключают совпавшие файлы.
+/// Шаблон с `/` в конце (`__pycache__/`) относится к каталогам, которые обрабатываются целиком.
+pub struct PatternSet {
+    include_names: GlobSet,
+    include_paths: GlobSet,
+    include_dirs: GlobSet,
+    exclude_names: GlobSet,
+    exclude_paths: GlobSet,
+    match_all: bool,
+}
+
+impl PatternSet {
+    /// Набор шаблонов включения: пустой список совпадает с любым файлом
+    pub fn new(patterns: &[String], case_insensitive: bool) -> Result<Self, CleanerError> {
+        Self::build(patterns, case_insensitive, true)
+    }
+
+    /// Набор шаблонов исключения: пустой список не совпадает ни с чем
+    pub fn exclusions(patterns: &[String], case_insensitive: bool) -> Result<Self, CleanerError> {
+        Self::build(patterns, case_insensitive, false)
+    }
+
+    fn build(patterns: &[String], case_insensitive: bool, empty_matches_all: bool) -> Result<Self, CleanerError> {
+        let mut include_names = GlobSetBuilder::new();
+        let mut include_paths = GlobSetBuilder::new();
+        let mut include_dirs = GlobSetBuilder::new();
+        let mut exclude_names = GlobSetBuilder::new();
+        let mut exclude_paths = GlobSetBuilder::new();
+
+        for pattern in patterns {
+            let (negated, pattern) = match pattern.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, pattern.as_str()),
+            };
+
+            let is_dir_pattern = !negated && pattern.len() > 1 && pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+
+            // Ведущий `/` лишь привязывает шаблон к корню категории
+            let pattern = pattern.trim_start_matches('/');
+            let is_path_pattern = pattern.contains('/');
+
+            // Каталог без `/` внутри шаблона ищется на любой глубине, как и имя файла
+            let glob_text = if is_dir_pattern && !is_path_pattern {
+                format!("**/{}", pattern)
+            } else {
+                pattern.to_string()
+            };
+
+            let glob = GlobBuilder::new(&glob_text)
+                .case_insensitive(case_insensitive)
+                .literal_separator(true)
+                .backslash_escape(true)
+                .build()
+                .map_err(|e| CleanerError::InvalidPattern {
+                    pattern: pattern.to_string(),
+                    reason: e.kind().to_string(),
+                })?;
+
+            let builder = match (negated, is_path_pattern, is_dir_pattern) {
+                (false, _, true) => &mut include_dirs,
+                (false, false, false) => &mut include_names,
+                (false, true, false) => &mut include_paths,
+                (true, false, _) => &mut exclude_names,
+                (true, true, _) => &mut exclude_paths,
+            };
+            builder.add(glob);
+        }
+
+        let build = |builder: GlobSetBuilder| {
+            builder.build().map_err(|e| CleanerError::InvalidPattern {
+                pattern: patterns.join(", "),
+                reason: e.kind().to_string(),
+            })
+        };
+
+        Ok(Self {
+            include_names: build(include_names)?,
+            include_paths: build(include_paths)?,
+            include_dirs: build(include_dirs)?,
+            exclude_names: build(exclude_names)?,
+            exclude_paths: build(exclude_paths)?,
+            // Список из одних отрицаний означает «все файлы, кроме исключённых»
+            match_all: match patterns.is_empty() {
+                true => empty_matches_all,
+                false => patterns.iter().all(|pattern| pattern.starts_with('!')),
+            },
+        })
+    }
+
+    /// Проверяет путь относительно корня категории
+    pub fn is_match(&self, relative: &Path) -> bool {
+        let file_name = match relative.file_name() {
+            Some(name) => Path::new(name),
+            None => return false,
+        };
+
+        if self.exclude_names.is_match(file_name) || self.exclude_paths.is_match(relative) {
+            return false;
+        }
+
+        self.match_all || self.include_names.is_match(file_name) || self.include_paths.is_match(relative)
+    }
+
+    /// Проверяет, подходит ли каталог под один из шаблонов каталогов (`name/`)
+    pub fn is_dir_match(&self, relative: &Path) -> bool {
+        if self.include_dirs.is_empty() {
+            return false;
+        }
+
+        let file_name = match relative.file_name() {
+            Some(name) => Path::new(name),
+            None => return false,
+        };
+
+        if self.exclude_names.is_match(file_name) || self.exclude_paths.is_match(relative) {
+            return false;
+        }
+
+        self.include_dirs.is_match(relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(patterns: &[&str]) -> PatternSet {
+        let patterns: Vec<String> = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        PatternSet::new(&patterns, false).unwrap()
+    }
+
+    #[test]
+    fn name_patterns_match_at_any_depth() {
+        let patterns = set(&["*.tmp"]);
+        assert!(patterns.is_match(Path::new("a.tmp")));
+        assert!(patterns.is_match(Path::new("deep/inside/a.tmp")));
+        assert!(!patterns.is_match(Path::new("a.tmpx")));
+    }
+
+    #[test]
+    fn path_patterns_are_anchored_to_the_root() {
+        let patterns = set(&["/logs/*.log", "cache/**"]);
+        assert!(patterns.is_match(Path::new("logs/app.log")));
+        assert!(!patterns.is_match(Path::new("other/logs/app.log")));
+        // `*` не переходит через `/`, `**` — переходит
+        assert!(!patterns.is_match(Path::new("logs/old/app.log")));
+        assert!(patterns.is_match(Path::new("cache/a/b/c")));
+    }
+
+    #[test]
+    fn negations_exclude_and_alone_match_everything_else() {
+        let patterns = set(&["*.log", "!keep.log", "!important/*"]);
+        assert!(patterns.is_match(Path::new("app.log")));
+        assert!(!patterns.is_match(Path::new("sub/keep.log")));
+        assert!(!patterns.is_match(Path::new("important/app.log")));
+
+        let only_negations = set(&["!*.conf"]);
+        assert!(only_negations.is_match(Path::new("anything.bin")));
+        assert!(!only_negations.is_match(Path::new("app.conf")));
+    }
+
+    #[test]
+    fn empty_list_matches_everything_but_excludes_nothing() {
+        assert!(set(&[]).is_match(Path::new("file")));
+        assert!(!PatternSet::exclusions(&[], false).unwrap().is_match(Path::new("file")));
+    }
+
+    #[test]
+    fn dir_patterns_match_directories_only() {
+        let patterns = set(&["__pycache__/", "/build/out/"]);
+        assert!(patterns.is_dir_match(Path::new("src/pkg/__pycache__")));
+        assert!(patterns.is_dir_match(Path::new("build/out")));
+        assert!(!patterns.is_dir_match(Path::new("src/build/out")));
+        // Шаблон каталога не выбирает одноимённые файлы
+        assert!(!patterns.is_match(Path::new("src/__pycache__")));
+    }
+
+    #[test]
+    fn case_insensitive_patterns() {
+        let patterns = PatternSet::new(&["*.TMP".to_string()], true).unwrap();
+        assert!(patterns.is_match(Path::new("a.tmp")));
+        assert!(!set(&["*.TMP"]).is_match(Path::new("a.tmp")));
+    }
+
+    #[test]
+    fn invalid_pattern_is_reported() {
+        let error = PatternSet::new(&["[unclosed".to_string()], false).err().unwrap();
+        assert!(matches!(error, CleanerError::InvalidPattern { pattern, .. } if pattern == "[unclosed"));
+    }
+}
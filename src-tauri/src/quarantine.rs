@@ -0,0 +1,597 @@
+use crate::cleaner::{translate_error, FileInfo};
+use crate::error::CleanerError;
+use chrono::{DateTime, Duration as ChronoDuration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const FILES_DIR: &str = "files";
+/// Записи, сделанные после последнего сохранения манифеста, по одной JSON-строке
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineManifest {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub entries: Vec<QuarantineEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub stored_name: String,
+    pub original_path: String,
+    pub category: String,
+    pub size: u64,
+    pub modified: u64,
+    pub checksum: String,
+    #[serde(default)]
+    pub kind: EntryKind,
+    /// Куда указывала символическая ссылка
+    #[serde(default)]
+    pub link_target: Option<String>,
+}
+
+/// Что записано в манифест. Содержимое хранится только у файлов; каталоги и ссылки
+/// при восстановлении создаются заново
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    File,
+    Symlink,
+    Directory,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineSessionInfo {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub total_files: usize,
+    pub total_size: u64,
+    pub entries: Vec<QuarantineEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreResult {
+    pub session_id: String,
+    pub files_restored: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeResult {
+    pub sessions_removed: usize,
+    pub space_freed: u64,
+    pub errors: Vec<String>,
+}
+
+/// Сессия карантина: файлы, перемещённые за одну очистку, и их манифест
+pub struct QuarantineSession {
+    dir: PathBuf,
+    manifest: QuarantineManifest,
+    /// Номер для имени следующего файла; не совпадает с числом записей, если перенос не удался
+    next_index: usize,
+}
+
+impl QuarantineSession {
+    pub fn create() -> Result<Self, CleanerError> {
+        let now = Local::now();
+        let id = now.format("%Y%m%d-%H%M%S-%3f").to_string();
+        let dir = get_quarantine_dir().join(&id);
+        std::fs::create_dir_all(dir.join(FILES_DIR))?;
+
+        let session = Self {
+            dir,
+            manifest: QuarantineManifest {
+                id,
+                created_at: now.with_timezone(&Utc),
+                entries: Vec::new(),
+            },
+            next_index: 0,
+        };
+
+        // Манифест нужен с самого начала: без него сессию прерванной очистки не найти
+        session.save()?;
+        Ok(session)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.manifest.id
+    }
+
+    /// Перемещает файл в карантин вместо удаления
+    pub fn store(&mut self, category: &str, file: &FileInfo) -> io::Result<()> {
+        let source = Path::new(&file.path);
+        let entry = QuarantineEntry {
+            stored_name: format!("{:06}", self.next_index),
+            original_path: file.path.clone(),
+            category: category.to_string(),
+            size: file.size,
+            modified: file.modified,
+            checksum: file_checksum(source)?,
+            kind: EntryKind::File,
+            link_target: None,
+        };
+        self.next_index += 1;
+
+        // Запись попадает в журнал до переноса: если программа завершится посреди очистки,
+        // уже перенесённые файлы всё равно можно будет восстановить
+        self.append_journal(&entry)?;
+        move_file(source, &self.dir.join(FILES_DIR).join(&entry.stored_name))?;
+
+        self.manifest.entries.push(entry);
+        Ok(())
+    }
+
+    fn append_journal(&self, entry: &QuarantineEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(JOURNAL_FILE))?
+            .write_all(line.as_bytes())
+    }
+
+    /// Переносит в карантин все файлы каталога по отдельности, записывает в манифест его подкаталоги
+    /// и символические ссылки, после чего удаляет опустевшие каталоги. Каналы, сокеты и устройства
+    /// восстановить нельзя, поэтому каталог с ними не трогается вовсе
+    pub fn store_directory(&mut self, category: &str, dir: &Path) -> io::Result<()> {
+        for entry in WalkDir::new(dir).follow_links(false) {
+            let file_type = entry.map_err(io::Error::from)?.file_type();
+            if !file_type.is_file() && !file_type.is_dir() && !file_type.is_symlink() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "в каталоге есть особые файлы (каналы, сокеты, устройства), в карантин его поместить нельзя",
+                ));
+            }
+        }
+
+        for entry in WalkDir::new(dir).follow_links(false) {
+            let entry = entry.map_err(io::Error::from)?;
+            let metadata = entry.metadata().map_err(io::Error::from)?;
+            let info = FileInfo::from_metadata(entry.path(), &metadata);
+
+            if metadata.is_file() {
+                self.store(category, &info)?;
+                continue;
+            }
+
+            let (kind, link_target) = if metadata.is_dir() {
+                (EntryKind::Directory, None)
+            } else {
+                let target = std::fs::read_link(entry.path())?;
+                (EntryKind::Symlink, Some(target.to_string_lossy().to_string()))
+            };
+
+            let record = QuarantineEntry {
+                stored_name: String::new(),
+                original_path: info.path,
+                category: category.to_string(),
+                size: 0,
+                modified: info.modified,
+                checksum: String::new(),
+                kind,
+                link_target,
+            };
+            self.append_journal(&record)?;
+            if kind == EntryKind::Symlink {
+                std::fs::remove_file(entry.path())?;
+            }
+            self.manifest.entries.push(record);
+        }
+
+        // Снизу вверх и только пустые: появившееся после обхода не пропадёт без записи в манифесте
+        for entry in WalkDir::new(dir).follow_links(false).contents_first(true) {
+            std::fs::remove_dir(entry.map_err(io::Error::from)?.path())?;
+        }
+        Ok(())
+    }
+
+    /// Записывает манифест целиком; журнал после этого больше не нужен
+    pub fn save(&self) -> Result<(), CleanerError> {
+        write_manifest(&self.dir, &self.manifest)
+    }
+
+    /// Завершает сессию. Пустая сессия удаляется целиком
+    pub fn finish(self) -> Result<(), CleanerError> {
+        if self.manifest.entries.is_empty() {
+            std::fs::remove_dir_all(&self.dir)?;
+            return Ok(());
+        }
+
+        self.save()
+    }
+}
+
+pub fn list_sessions() -> Result<Vec<QuarantineSessionInfo>, CleanerError> {
+    let root = get_quarantine_dir();
+    let mut sessions = Vec::new();
+
+    if !root.exists() {
+        return Ok(sessions);
+    }
+
+    for entry in std::fs::read_dir(&root)? {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+
+        // Каталоги без манифеста (например, прерванные сессии) пропускаем
+        let manifest = match read_manifest(&entry.path()) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        sessions.push(QuarantineSessionInfo {
+            id: manifest.id,
+            created_at: manifest.created_at,
+            total_files: manifest.entries.iter().filter(|e| e.kind == EntryKind::File).count(),
+            total_size: manifest.entries.iter().map(|e| e.size).sum(),
+            entries: manifest.entries,
+        });
+    }
+
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.created_at));
+    Ok(sessions)
+}
+
+/// Возвращает файлы сессии на исходные места. Если `paths` пуст, восстанавливается вся сессия;
+/// путь каталога восстанавливает и всё, что в нём лежало
+pub fn restore(session_id: &str, paths: &[String]) -> Result<RestoreResult, CleanerError> {
+    restore_from(&session_dir(session_id)?, session_id, paths)
+}
+
+fn restore_from(dir: &Path, session_id: &str, paths: &[String]) -> Result<RestoreResult, CleanerError> {
+    let mut manifest = read_manifest(dir)?;
+    let mut files_restored = 0;
+    let mut errors = Vec::new();
+    let mut remaining = Vec::new();
+
+    for entry in manifest.entries {
+        let selected = paths.is_empty()
+            || paths.iter().any(|path| Path::new(&entry.original_path).starts_with(path));
+        if !selected {
+            remaining.push(entry);
+            continue;
+        }
+
+        match restore_entry(dir, &entry) {
+            Ok(_) => files_restored += 1,
+            Err(e) => {
+                errors.push(format!("{}: {}", entry.original_path, e));
+                remaining.push(entry);
+            }
+        }
+    }
+
+    manifest.entries = remaining;
+    if manifest.entries.is_empty() {
+        std::fs::remove_dir_all(dir)?;
+    } else {
+        write_manifest(dir, &manifest)?;
+    }
+
+    Ok(RestoreResult {
+        session_id: session_id.to_string(),
+        files_restored,
+        errors,
+    })
+}
+
+/// Удаляет сессии карантина старше `retention_days` дней
+pub fn purge(retention_days: u32) -> Result<PurgeResult, CleanerError> {
+    let cutoff = Utc::now() - ChronoDuration::days(retention_days as i64);
+    let mut sessions_removed = 0;
+    let mut space_freed = 0u64;
+    let mut errors = Vec::new();
+
+    for session in list_sessions()? {
+        if session.created_at > cutoff {
+            continue;
+        }
+
+        match std::fs::remove_dir_all(get_quarantine_dir().join(&session.id)) {
+            Ok(_) => {
+                sessions_removed += 1;
+                space_freed += session.total_size;
+            }
+            Err(e) => errors.push(format!("{}: {}", session.id, translate_error(&e))),
+        }
+    }
+
+    Ok(PurgeResult {
+        sessions_removed,
+        space_freed,
+        errors,
+    })
+}
+
+fn restore_entry(dir: &Path, entry: &QuarantineEntry) -> Result<(), String> {
+    let stored = dir.join(FILES_DIR).join(&entry.stored_name);
+    let target = Path::new(&entry.original_path);
+
+    // Каталог мог уже появиться снова — тогда восстанавливать в нём только содержимое
+    if entry.kind == EntryKind::Directory {
+        return std::fs::create_dir_all(target).map_err(|e| translate_error(&e));
+    }
+
+    if std::fs::symlink_metadata(target).is_ok() {
+        return Err("Файл с таким именем уже существует".to_string());
+    }
+
+    if entry.kind == EntryKind::Symlink {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| translate_error(&e))?;
+        }
+        let link_target = entry.link_target.as_deref().unwrap_or_default();
+        return create_symlink(Path::new(link_target), target).map_err(|e| translate_error(&e));
+    }
+
+    let checksum = file_checksum(&stored).map_err(|e| translate_error(&e))?;
+    if checksum != entry.checksum {
+        return Err("Контрольная сумма не совпадает".to_string());
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| translate_error(&e))?;
+    }
+
+    move_file(&stored, target).map_err(|e| translate_error(&e))
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    // Цель могла ещё не восстановиться, поэтому тип ссылки берём по ней, если она есть
+    let resolved = link.parent().map_or_else(|| target.to_path_buf(), |parent| parent.join(target));
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+fn session_dir(session_id: &str) -> Result<PathBuf, CleanerError> {
+    // Идентификатор приходит из интерфейса, поэтому не даём выйти за пределы каталога карантина
+    if session_id.is_empty() || session_id.contains(['/', '\\']) || session_id.contains("..") {
+        return Err(CleanerError::InvalidPath { path: session_id.to_string() });
+    }
+
+    let dir = get_quarantine_dir().join(session_id);
+    if !dir.join(MANIFEST_FILE).exists() {
+        return Err(CleanerError::InvalidPath { path: dir.to_string_lossy().to_string() });
+    }
+
+    Ok(dir)
+}
+
+/// Читает манифест вместе с записями журнала, которые не успели в него попасть
+fn read_manifest(dir: &Path) -> Result<QuarantineManifest, CleanerError> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST_FILE))?;
+    let mut manifest: QuarantineManifest = serde_json::from_str(&content)?;
+
+    if let Ok(journal) = std::fs::read_to_string(dir.join(JOURNAL_FILE)) {
+        // У каталогов и ссылок нет имени в хранилище, их отличает исходный путь
+        let key = |entry: &QuarantineEntry| (entry.stored_name.clone(), entry.original_path.clone());
+        let known: HashSet<(String, String)> = manifest.entries.iter().map(key).collect();
+
+        // Последняя строка могла оборваться, а файл — так и не переехать: такие записи отбрасываются
+        let pending = journal
+            .lines()
+            .filter_map(|line| serde_json::from_str::<QuarantineEntry>(line).ok())
+            .filter(|entry| !known.contains(&key(entry)))
+            .filter(|entry| entry.kind != EntryKind::File || dir.join(FILES_DIR).join(&entry.stored_name).exists());
+        manifest.entries.extend(pending);
+    }
+
+    Ok(manifest)
+}
+
+/// Атомарно заменяет манифест и удаляет журнал, записи которого в него вошли
+fn write_manifest(dir: &Path, manifest: &QuarantineManifest) -> Result<(), CleanerError> {
+    let content = serde_json::to_string_pretty(manifest)?;
+    let temp = dir.join(format!(".{}.tmp", MANIFEST_FILE));
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, dir.join(MANIFEST_FILE))?;
+
+    match std::fs::remove_file(dir.join(JOURNAL_FILE)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Переименовывает файл, а если источник и цель на разных дисках — копирует и удаляет оригинал
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(from, to)?;
+    if let Err(e) = std::fs::remove_file(from) {
+        let _ = std::fs::remove_file(to);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+fn file_checksum(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+pub fn get_quarantine_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("cleaner")
+        .join("quarantine")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(stored_name: &str) -> QuarantineEntry {
+        QuarantineEntry {
+            stored_name: stored_name.to_string(),
+            original_path: format!("/tmp/{}", stored_name),
+            category: "temp_files".to_string(),
+            size: 1,
+            modified: 0,
+            checksum: String::new(),
+            kind: EntryKind::File,
+            link_target: None,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cleaner-quarantine-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("session").join(FILES_DIR)).unwrap();
+        dir
+    }
+
+    fn session(dir: &Path) -> QuarantineSession {
+        QuarantineSession {
+            dir: dir.to_path_buf(),
+            manifest: QuarantineManifest {
+                id: "session".to_string(),
+                created_at: Utc::now(),
+                entries: Vec::new(),
+            },
+            next_index: 0,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn store_directory_restores_links_and_empty_dirs() {
+        let root = temp_dir("tree");
+        let tree = root.join("tree");
+        std::fs::create_dir_all(tree.join("empty")).unwrap();
+        std::fs::write(tree.join("a.txt"), b"content").unwrap();
+        std::os::unix::fs::symlink("a.txt", tree.join("link")).unwrap();
+
+        let mut session = session(&root.join("session"));
+        session.store_directory("cache", &tree).unwrap();
+        assert!(!tree.exists());
+
+        for entry in &session.manifest.entries {
+            restore_entry(&session.dir, entry).unwrap();
+        }
+        assert!(tree.join("empty").is_dir());
+        assert_eq!(std::fs::read(tree.join("a.txt")).unwrap(), b"content");
+        assert_eq!(std::fs::read_link(tree.join("link")).unwrap(), Path::new("a.txt"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn restoring_a_directory_restores_its_contents() {
+        let root = temp_dir("nested");
+        let tree = root.join("tree");
+        let sibling = root.join("treehouse");
+        std::fs::create_dir_all(tree.join("sub/deeper")).unwrap();
+        std::fs::create_dir_all(&sibling).unwrap();
+        std::fs::write(tree.join("a.txt"), b"a").unwrap();
+        std::fs::write(tree.join("sub/deeper/b.txt"), b"b").unwrap();
+        std::fs::write(sibling.join("c.txt"), b"c").unwrap();
+
+        let mut session = session(&root.join("session"));
+        session.store_directory("cache", &tree).unwrap();
+        session.store_directory("cache", &sibling).unwrap();
+        session.save().unwrap();
+
+        let result = restore_from(&session.dir, "session", &[tree.to_string_lossy().to_string()]).unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.files_restored, 5);
+        assert_eq!(std::fs::read(tree.join("a.txt")).unwrap(), b"a");
+        assert_eq!(std::fs::read(tree.join("sub/deeper/b.txt")).unwrap(), b"b");
+
+        // Каталог с тем же началом имени остаётся в карантине
+        assert!(!sibling.exists());
+        let left: Vec<String> = read_manifest(&session.dir).unwrap().entries.into_iter().map(|entry| entry.original_path).collect();
+        assert_eq!(left, [sibling.to_string_lossy().to_string(), sibling.join("c.txt").to_string_lossy().to_string()]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn store_directory_refuses_special_files() {
+        let root = temp_dir("special");
+        let tree = root.join("tree");
+        std::fs::create_dir_all(&tree).unwrap();
+        std::fs::write(tree.join("a.txt"), b"content").unwrap();
+        let _socket = std::os::unix::net::UnixListener::bind(tree.join("socket")).unwrap();
+
+        let mut session = session(&root.join("session"));
+        assert!(session.store_directory("cache", &tree).is_err());
+        assert!(tree.join("a.txt").exists());
+        assert!(session.manifest.entries.is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn read_manifest_recovers_journal_entries() {
+        let root = temp_dir("journal");
+        let dir = root.join("session");
+
+        let manifest = QuarantineManifest {
+            id: "session".to_string(),
+            created_at: Utc::now(),
+            entries: vec![entry("000000")],
+        };
+        write_manifest(&dir, &manifest).unwrap();
+        for name in ["000000", "000001"] {
+            std::fs::write(dir.join(FILES_DIR).join(name), b"x").unwrap();
+        }
+
+        // Уже сохранённая запись, перенесённый файл, непереехавший файл и оборванная строка
+        let mut journal = String::new();
+        for name in ["000000", "000001", "000002"] {
+            journal.push_str(&serde_json::to_string(&entry(name)).unwrap());
+            journal.push('\n');
+        }
+        journal.push_str("{\"stored_name\":\"0000");
+        std::fs::write(dir.join(JOURNAL_FILE), journal).unwrap();
+
+        let recovered = read_manifest(&dir).unwrap();
+        let names: Vec<&str> = recovered.entries.iter().map(|entry| entry.stored_name.as_str()).collect();
+        assert_eq!(names, ["000000", "000001"]);
+
+        write_manifest(&dir, &recovered).unwrap();
+        assert!(!dir.join(JOURNAL_FILE).exists());
+        assert_eq!(read_manifest(&dir).unwrap().entries.len(), 2);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
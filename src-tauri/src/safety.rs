@@ -0,0 +1,203 @@
+use crate::config::get_config_path;
+use crate::error::CleanerError;
+use crate::quarantine::get_quarantine_dir;
+use crate::scan_index::get_index_path;
+use std::path::{Component, Path, PathBuf};
+
+/// Встроенный список защищённых путей, которые движок не трогает в безопасном режиме
+#[derive(Clone)]
+pub struct ProtectedPaths {
+    /// Сами эти каталоги не могут быть корнем категории, но их содержимое можно чистить
+    roots: Vec<PathBuf>,
+    /// Эти каталоги защищены целиком, вместе со всем содержимым
+    subtrees: Vec<PathBuf>,
+    /// Каталоги внутри защищённых деревьев, где лежат только одноразовые данные
+    allowed: Vec<PathBuf>,
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        let mut roots = Vec::new();
+        let mut subtrees = Vec::new();
+        let mut allowed = Vec::new();
+
+        // Корни файловых систем
+        roots.push(PathBuf::from("/"));
+        for drive in b'A'..=b'Z' {
+            roots.push(PathBuf::from(format!("{}:\\", drive as char)));
+        }
+
+        // Каталоги пользователей
+        roots.push(PathBuf::from("/home"));
+        roots.push(PathBuf::from("/Users"));
+        roots.push(PathBuf::from("/root"));
+        roots.push(PathBuf::from("C:\\Users"));
+        roots.extend(dirs::home_dir());
+        roots.extend(dirs::desktop_dir());
+        roots.extend(dirs::document_dir());
+        roots.extend(dirs::picture_dir());
+        roots.extend(dirs::audio_dir());
+        roots.extend(dirs::video_dir());
+
+        // Системные каталоги Windows
+        roots.push(PathBuf::from("C:\\Windows"));
+        roots.push(PathBuf::from("C:\\ProgramData"));
+        subtrees.push(PathBuf::from("C:\\Windows\\System32"));
+        subtrees.push(PathBuf::from("C:\\Windows\\SysWOW64"));
+        subtrees.push(PathBuf::from("C:\\Windows\\WinSxS"));
+        subtrees.push(PathBuf::from("C:\\Windows\\Installer"));
+        subtrees.push(PathBuf::from("C:\\Windows\\Boot"));
+        subtrees.push(PathBuf::from("C:\\Program Files"));
+        subtrees.push(PathBuf::from("C:\\Program Files (x86)"));
+
+        // Системные каталоги Unix
+        roots.push(PathBuf::from("/var"));
+        roots.push(PathBuf::from("/opt"));
+        for dir in ["/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/run", "/sbin", "/sys", "/usr", "/var/lib", "/System", "/Library"] {
+            subtrees.push(PathBuf::from(dir));
+        }
+        // Дампы памяти systemd-coredump никому не нужны после разбора падения; остальная /var/lib/systemd защищена
+        allowed.push(PathBuf::from("/var/lib/systemd/coredump"));
+
+        // Собственные данные приложения: конфигурация, карантин и индекс сканирования
+        if let Some(config_dir) = get_config_path().parent() {
+            subtrees.push(config_dir.to_path_buf());
+        }
+        subtrees.push(get_quarantine_dir());
+        subtrees.push(get_index_path());
+
+        // Сравниваем с реальными путями, чтобы символические ссылки (например, /lib -> /usr/lib) не обходили защиту
+        let resolve = |paths: Vec<PathBuf>| -> Vec<PathBuf> {
+            let mut resolved = Vec::new();
+            for path in paths {
+                if let Ok(canonical) = path.canonicalize() {
+                    if canonical != path {
+                        resolved.push(canonical);
+                    }
+                }
+                resolved.push(path);
+            }
+            resolved
+        };
+
+        Self {
+            roots: resolve(roots),
+            subtrees: resolve(subtrees),
+            allowed: resolve(allowed),
+        }
+    }
+}
+
+impl ProtectedPaths {
+    /// Проверяет корень категории: он не должен совпадать с защищённым путём или лежать внутри защищённого дерева.
+    /// Возвращает реальный путь корня
+    pub fn check_category_root(&self, path: &Path) -> Result<PathBuf, CleanerError> {
+        check_form(path)?;
+        let resolved = path.canonicalize().map_err(|_| CleanerError::InvalidPath {
+            path: path.to_string_lossy().to_string(),
+        })?;
+
+        self.check_entry(&resolved)?;
+        Ok(resolved)
+    }
+
+    /// Проверяет отдельный файл или каталог внутри уже проверенного корня (без обращения к диску)
+    pub fn check_entry(&self, path: &Path) -> Result<(), CleanerError> {
+        let allowed = self.allowed.iter().any(|dir| path.starts_with(dir));
+        if !allowed && self.subtrees.iter().any(|subtree| path.starts_with(subtree)) {
+            return Err(CleanerError::PermissionDenied {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+
+        if self.roots.iter().any(|root| root == path) {
+            return Err(CleanerError::PermissionDenied {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Проверяет файл перед удалением. Разрешается только родительский каталог, сам файл-ссылка не раскрывается
+    pub fn check_removal(&self, path: &Path) -> Result<(), CleanerError> {
+        let invalid = || CleanerError::InvalidPath {
+            path: path.to_string_lossy().to_string(),
+        };
+
+        check_form(path)?;
+        let parent = path.parent().ok_or_else(invalid)?;
+        let file_name = path.file_name().ok_or_else(invalid)?;
+        let resolved = parent.canonicalize().map_err(|_| invalid())?.join(file_name);
+
+        self.check_entry(&resolved)
+    }
+}
+
+/// Путь должен быть абсолютным и без `..`: относительный зависит от текущего каталога,
+/// а `..` уводит туда, чего не видно в самом пути
+fn check_form(path: &Path) -> Result<(), CleanerError> {
+    if !path.is_absolute() || path.components().any(|component| component == Component::ParentDir) {
+        return Err(CleanerError::InvalidPath {
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn is_denied(result: Result<impl Sized, CleanerError>) -> bool {
+        matches!(result, Err(CleanerError::PermissionDenied { .. }))
+    }
+
+    fn is_invalid(result: Result<impl Sized, CleanerError>) -> bool {
+        matches!(result, Err(CleanerError::InvalidPath { .. }))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn protected_roots_and_subtrees_are_denied() {
+        let protected = ProtectedPaths::default();
+
+        // Сам корень защищён, его содержимое — нет
+        assert!(is_denied(protected.check_entry(Path::new("/home"))));
+        assert!(is_denied(protected.check_category_root(Path::new("/"))));
+        assert!(protected.check_entry(Path::new("/home/user/.cache")).is_ok());
+
+        // Защищённое дерево закрыто целиком
+        assert!(is_denied(protected.check_category_root(Path::new("/etc"))));
+        assert!(is_denied(protected.check_entry(Path::new("/usr/lib/libc.so.6"))));
+        assert!(is_denied(protected.check_removal(Path::new("/etc/hostname"))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relative_and_escaping_paths_are_invalid() {
+        let protected = ProtectedPaths::default();
+        let temp = std::env::temp_dir();
+
+        assert!(is_invalid(protected.check_category_root(Path::new("."))));
+        assert!(is_invalid(protected.check_category_root(Path::new("relative/dir"))));
+        assert!(is_invalid(protected.check_category_root(&temp.join("../etc"))));
+        assert!(is_invalid(protected.check_removal(&temp.join("x/../../etc/hostname"))));
+        assert!(is_invalid(protected.check_removal(Path::new("file.tmp"))));
+        assert!(protected.check_category_root(&temp).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn default_core_dumps_category_is_not_refused() {
+        let protected = ProtectedPaths::default();
+        let config = AppConfig::default();
+
+        for root in &config.cleanup_categories["core_dumps"].paths {
+            assert!(protected.check_entry(root).is_ok());
+            assert!(protected.check_entry(&root.join("core.app.1000.boot.42.1700000000.zst")).is_ok());
+        }
+        assert!(protected.check_entry(Path::new("/var/lib/systemd/linger")).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use crate::cleaner::{CutOff, FileInfo};
+use crate::config::{AppConfig, CleanupCategory};
+use crate::error::CleanerError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Версия формата: индекс другой версии просто не используется
+const INDEX_VERSION: u32 = 2;
+
+/// Сколько файлов категории хранится в индексе. Категории с шаблоном `*` в больших каталогах
+/// раздували бы индекс до сотен мегабайт, поэтому сверх этого порога они сканируются заново целиком
+const MAX_INDEXED_FILES: usize = 100_000;
+
+/// Номер для имени временного файла: несколько сканирований могут сохранять индекс одновременно
+static SAVE_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Индекс прошлого сканирования: по каждой категории — содержимое прочитанных каталогов.
+///
+/// Каталог считается неизменившимся, пока совпадает его время изменения. Оно меняется, когда в каталоге
+/// появляются, исчезают или переименовываются записи, но не когда переписывается содержимое файла,
+/// поэтому метаданные файлов из индекса при каждом сканировании перечитываются, а категории,
+/// считающие возраст по времени доступа, индекс не используют.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanIndex {
+    version: u32,
+    categories: HashMap<String, CategoryIndex>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CategoryIndex {
+    /// Отпечаток фильтров, с которыми собран индекс
+    fingerprint: String,
+    /// Сколько каталогов было прочитано, даже если сами записи в индекс не попали
+    dir_count: usize,
+    dirs: HashMap<String, DirRecord>,
+}
+
+/// Содержимое одного каталога на момент прошлого сканирования
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirRecord {
+    pub modified_secs: u64,
+    pub modified_nanos: u32,
+    /// Файлы, подошедшие по шаблону; возраст и размер проверяются заново при каждом сканировании
+    pub files: Vec<IndexedFile>,
+    pub errors: Vec<String>,
+    /// Ссылки на каталоги среди записей каталога, в которые обход не заходил
+    #[serde(default)]
+    pub cut_off: Vec<CutOff>,
+    pub subdirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub file: FileInfo,
+    /// Отметка времени, по которой категория считает возраст (секунды с UNIX_EPOCH)
+    pub timestamp: u64,
+}
+
+impl ScanIndex {
+    /// Загружает индекс. Отсутствующий, повреждённый или устаревший индекс равносилен пустому
+    pub fn load() -> Self {
+        std::fs::read_to_string(get_index_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<ScanIndex>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), CleanerError> {
+        self.save_to(&get_index_path())
+    }
+
+    /// Записывает индекс во временный файл рядом и переименовывает его: прерванная запись
+    /// не оставляет вместо индекса обрезанный файл
+    fn save_to(&self, path: &Path) -> Result<(), CleanerError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let sequence = SAVE_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(format!(".{}-{}.tmp", std::process::id(), sequence));
+        let temp_path = path.with_file_name(temp_name);
+
+        let content = serde_json::to_vec(self)?;
+        if let Err(error) = std::fs::write(&temp_path, content).and_then(|()| std::fs::rename(&temp_path, path)) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Индекс категории, если он собран с теми же фильтрами
+    pub fn category(&self, name: &str, fingerprint: &str) -> Option<&CategoryIndex> {
+        self.categories
+            .get(name)
+            .filter(|index| index.fingerprint == fingerprint)
+    }
+
+    /// Сколько каталогов категории прочитано в прошлый раз, даже если её фильтры с тех пор изменились
+    pub fn dir_count(&self, name: &str) -> usize {
+        self.categories.get(name).map_or(0, |index| index.dir_count)
+    }
+
+    /// Заменяет индексы просканированных категорий, индексы остальных выбрасывает
+    pub fn replace(&mut self, categories: HashMap<String, CategoryIndex>) {
+        self.version = INDEX_VERSION;
+        self.categories = categories;
+    }
+}
+
+impl CategoryIndex {
+    /// Индекс категории. Если файлов больше [`MAX_INDEXED_FILES`], каталоги не сохраняются
+    /// и в следующий раз категория читается с диска
+    pub fn new(fingerprint: String, mut dirs: HashMap<String, DirRecord>) -> Self {
+        let dir_count = dirs.len();
+        let files: usize = dirs.values().map(|record| record.files.len()).sum();
+        if files > MAX_INDEXED_FILES {
+            dirs.clear();
+        }
+        Self { fingerprint, dir_count, dirs }
+    }
+
+    pub fn dir(&self, path: &str) -> Option<&DirRecord> {
+        self.dirs.get(path)
+    }
+}
+
+/// Отпечаток всего, что влияет на результат сканирования категории: её настроек и глобальных фильтров
+pub fn fingerprint(config: &AppConfig, category: &CleanupCategory) -> String {
+    let filters = serde_json::json!({
+        "category": category,
+        "safe_mode": config.safe_mode,
+        "max_file_size_mb": config.max_file_size_mb,
+        "excluded_paths": config.excluded_paths,
+        "excluded_patterns": config.excluded_patterns,
+    });
+
+    format!("{:x}", Sha256::digest(filters.to_string().as_bytes()))
+}
+
+pub fn get_index_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("cleaner")
+        .join("scan_index.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("scan_index_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn record(files: usize) -> DirRecord {
+        DirRecord {
+            modified_secs: 0,
+            modified_nanos: 0,
+            files: (0..files)
+                .map(|n| IndexedFile {
+                    file: FileInfo {
+                        path: format!("/dir/{}", n),
+                        size: 1,
+                        allocated: 4096,
+                        file_id: None,
+                        links: 1,
+                        modified: 0,
+                        is_directory: false,
+                    },
+                    timestamp: 0,
+                })
+                .collect(),
+            errors: vec![],
+            cut_off: vec![],
+            subdirs: vec![],
+        }
+    }
+
+    #[test]
+    fn save_replaces_the_index_without_leaving_temp_files() {
+        let dir = temp_dir("save");
+        let path = dir.join("scan_index.json");
+        std::fs::write(&path, b"old").unwrap();
+
+        let mut index = ScanIndex::default();
+        index.replace(HashMap::from([(
+            "fixture".to_string(),
+            CategoryIndex::new("print".to_string(), HashMap::from([("/dir".to_string(), record(2))])),
+        )]));
+        index.save_to(&path).unwrap();
+
+        let loaded: ScanIndex = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(loaded.category("fixture", "print").unwrap().dir("/dir").unwrap().files.len(), 2);
+        let names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(names, ["scan_index.json"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn oversized_category_keeps_only_the_dir_count() {
+        let dirs = HashMap::from([
+            ("/a".to_string(), record(MAX_INDEXED_FILES)),
+            ("/b".to_string(), record(1)),
+        ]);
+        let index = CategoryIndex::new("print".to_string(), dirs);
+        assert!(index.dir("/a").is_none());
+        assert_eq!(index.dir_count, 2);
+
+        let small = CategoryIndex::new("print".to_string(), HashMap::from([("/a".to_string(), record(1))]));
+        assert!(small.dir("/a").is_some());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::cleaner::link_count;
+use crate::mounts::{self, MountEntry};
+use std::fs::OpenOptions;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Файловые системы, которые пишут новые данные в другие блоки (copy-on-write и журнальные по логу):
+/// перезапись не попадает туда, где лежало старое содержимое
+const RELOCATING_FILESYSTEMS: [&str; 6] = ["btrfs", "zfs", "bcachefs", "f2fs", "nilfs2", "jffs2"];
+
+/// Сетевые файловые системы: что происходит с блоками на сервере, неизвестно
+const NETWORK_FILESYSTEMS: [&str; 6] = ["nfs", "nfs4", "cifs", "smb3", "sshfs", "fuse.sshfs"];
+
+/// Почему затирание на этом разделе не гарантирует, что данные нельзя восстановить
+pub fn untrusted_reason(mount: &MountEntry) -> Option<String> {
+    let fs_type = mount.fs_type.as_str();
+    let mount_point = mount.mount_point.display();
+
+    if RELOCATING_FILESYSTEMS.contains(&fs_type) {
+        return Some(format!(
+            "{}: файловая система {} записывает данные на новое место, старое содержимое может сохраниться",
+            mount_point, fs_type
+        ));
+    }
+
+    if NETWORK_FILESYSTEMS.contains(&fs_type) {
+        return Some(format!("{}: сетевая файловая система {}, затирание на сервере не гарантируется", mount_point, fs_type));
+    }
+
+    if mounts::is_solid_state(&mount.device) {
+        return Some(format!(
+            "{}: твердотельный накопитель перераспределяет запись, старые блоки могут сохраниться до TRIM",
+            mount_point
+        ));
+    }
+
+    None
+}
+
+/// Проверяет, что затирание не заденет другие имена: ни у файла, ни у файлов внутри каталога
+/// не должно быть других жёстких ссылок, иначе затёрлось бы содержимое, видимое по ним
+pub fn check_links(path: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(path) {
+        let entry = entry.map_err(io::Error::from)?;
+        if entry.file_type().is_file() && link_count(&entry.metadata().map_err(io::Error::from)?) > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: у файла есть другие жёсткие ссылки, затирание испортило бы их содержимое", entry.path().display()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Перезаписывает файл `passes` раз, переименовывает его, чтобы скрыть имя, и удаляет.
+/// Символические ссылки не раскрываются; файл с другими жёсткими ссылками не трогается, см. [`check_links`]
+pub fn shred_file(path: &Path, passes: u32) -> io::Result<()> {
+    check_links(path)?;
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.is_file() {
+        overwrite(path, metadata.len(), passes)?;
+    }
+
+    let hidden = hide_name(path)?;
+    std::fs::remove_file(hidden)
+}
+
+/// Затирает все файлы каталога и удаляет его. Имена вложенных каталогов и самого каталога
+/// скрываются так же, как имена файлов. Ссылки проверяются заранее, чтобы не бросить каталог затёртым наполовину
+pub fn shred_dir(path: &Path, passes: u32) -> io::Result<()> {
+    check_links(path)?;
+    for entry in WalkDir::new(path).contents_first(true) {
+        let entry = entry.map_err(io::Error::from)?;
+        if entry.file_type().is_dir() {
+            // Каталог уже пуст и прочитан обходом, переименование обходу не мешает
+            let hidden = hide_name(entry.path())?;
+            std::fs::remove_dir(hidden)?;
+        } else {
+            shred_file(entry.path(), passes)?;
+        }
+    }
+    Ok(())
+}
+
+fn overwrite(path: &Path, len: u64, passes: u32) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    let mut file = options.open(path)?;
+
+    let mut noise = Noise::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    for _ in 0..passes {
+        file.seek(SeekFrom::Start(0))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk = remaining.min(BUFFER_SIZE as u64) as usize;
+            noise.fill(&mut buffer[..chunk]);
+            file.write_all(&buffer[..chunk])?;
+            remaining -= chunk as u64;
+        }
+        // Каждый проход должен дойти до диска, иначе кеш страниц схлопнет их в одну запись
+        file.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// Переименовывает файл или каталог в строку нулей той же длины, чтобы имя не осталось в родителе
+fn hide_name(path: &Path) -> io::Result<PathBuf> {
+    let len = path.file_name().map_or(1, |name| name.len()).max(1);
+
+    for attempt in 0..100 {
+        let name = if attempt == 0 {
+            "0".repeat(len)
+        } else {
+            format!("{:0>width$}", attempt, width = len)
+        };
+        let hidden = path.with_file_name(name);
+        if std::fs::symlink_metadata(&hidden).is_err() {
+            std::fs::rename(path, &hidden)?;
+            return Ok(hidden);
+        }
+    }
+
+    // Все варианты заняты — удаляем под исходным именем
+    Ok(path.to_path_buf())
+}
+
+/// Быстрый генератор псевдослучайных байт (xorshift64*). Криптостойкость не нужна:
+/// важно только, чтобы поверх старых данных легло что-то, не связанное с ними
+struct Noise(u64);
+
+impl Noise {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self(seed | 1)
+    }
+
+    fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(8) {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            let value = self.0.wrapping_mul(0x2545_F491_4F6C_DD1D).to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shred-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn contents_are_overwritten_before_unlink() {
+        let dir = temp_dir("contents");
+        let path = dir.join("secret.txt");
+        let secret = b"password=hunter2\n".repeat(5000);
+        std::fs::write(&path, &secret).unwrap();
+
+        // Открытый дескриптор видит тот же inode и после удаления имени
+        let mut reader = std::fs::File::open(&path).unwrap();
+        shred_file(&path, 2).unwrap();
+
+        assert!(std::fs::read_dir(&dir).unwrap().next().is_none());
+        let mut left = Vec::new();
+        reader.read_to_end(&mut left).unwrap();
+        assert_eq!(left.len(), secret.len());
+        assert!(!left.windows(8).any(|window| window == b"password"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hidden_name_does_not_keep_the_original() {
+        let dir = temp_dir("names");
+        let path = dir.join("tax-return-2024.pdf");
+        std::fs::write(&path, b"data").unwrap();
+        std::fs::write(dir.join("0".repeat(19)), b"taken").unwrap();
+
+        let hidden = hide_name(&path).unwrap();
+        let name = hidden.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name, format!("{:0>19}", 1));
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(&hidden).unwrap(), b"data");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_refused() {
+        let dir = temp_dir("links");
+        let path = dir.join("shared.txt");
+        std::fs::write(&path, b"shared").unwrap();
+        std::fs::hard_link(&path, dir.join("other.txt")).unwrap();
+
+        assert!(shred_file(&path, 1).is_err());
+        assert!(shred_dir(&dir, 1).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"shared");
+        assert_eq!(std::fs::read(dir.join("other.txt")).unwrap(), b"shared");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,600 @@
+use crate::cleaner::current_uid;
+use crate::config::xdg_home;
+use crate::mounts::MountTable;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const FILES_DIR: &str = "files";
+const INFO_DIR: &str = "info";
+const INFO_EXTENSION: &str = "trashinfo";
+const DIRECTORY_SIZES: &str = "directorysizes";
+const DELETION_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Описание без файла моложе этого может принадлежать перемещению, которое ещё идёт:
+/// по спецификации описание создаётся раньше, чем файл попадает в `files/`
+const ORPHANED_INFO_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Sticky-бит: в общем `.Trash` пользователи не могут удалять чужие каталоги
+#[cfg(unix)]
+const STICKY_BIT: u32 = 0o1000;
+
+/// Каталог корзины по спецификации freedesktop.org: `files/` с самими файлами и `info/` с их описаниями
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashDir {
+    pub root: PathBuf,
+    /// Корень раздела для корзин `$topdir/.Trash*`; пути в них записываются относительно него.
+    /// У домашней корзины не задан
+    pub topdir: Option<PathBuf>,
+}
+
+impl TrashDir {
+    pub fn files_dir(&self) -> PathBuf {
+        self.root.join(FILES_DIR)
+    }
+
+    pub fn info_dir(&self) -> PathBuf {
+        self.root.join(INFO_DIR)
+    }
+}
+
+/// Элемент корзины: запись в `files/` и сведения из её описания
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    /// Путь в `files/`
+    pub path: PathBuf,
+    pub name: OsString,
+    /// Когда элемент попал в корзину: `DeletionDate` из описания, а если её нет — время изменения описания.
+    /// У элемента без описания не задано
+    pub deleted_at: Option<SystemTime>,
+    /// Время изменения описания в секундах; по нему проверяется кеш `directorysizes`
+    pub info_modified: Option<u64>,
+}
+
+/// Домашняя корзина: `$XDG_DATA_HOME/Trash`, по умолчанию `~/.local/share/Trash`
+pub fn home_trash() -> Option<TrashDir> {
+    Some(TrashDir {
+        root: xdg_home("XDG_DATA_HOME", ".local/share")?.join("Trash"),
+        topdir: None,
+    })
+}
+
+/// Все существующие корзины пользователя: домашняя и `.Trash/$uid`, `.Trash-$uid` в корнях смонтированных разделов
+#[cfg(unix)]
+pub fn trash_dirs(mounts: &MountTable) -> Vec<TrashDir> {
+    let mut dirs: Vec<TrashDir> = home_trash().into_iter().collect();
+    if let Some(uid) = current_uid() {
+        for mount in mounts.entries() {
+            let topdir = &mount.mount_point;
+            let shared = shared_trash(topdir).map(|shared| shared.join(uid.to_string()));
+            let own = topdir.join(format!(".Trash-{}", uid));
+            dirs.extend(shared.into_iter().chain(Some(own)).map(|root| TrashDir {
+                root,
+                topdir: Some(topdir.clone()),
+            }));
+        }
+    }
+
+    // Ссылку вместо каталога корзины спецификация велит не трогать
+    let mut seen = HashSet::new();
+    dirs.retain(|trash| {
+        std::fs::symlink_metadata(&trash.root).is_ok_and(|metadata| metadata.is_dir()) && seen.insert(trash.root.clone())
+    });
+    dirs
+}
+
+#[cfg(not(unix))]
+pub fn trash_dirs(_mounts: &MountTable) -> Vec<TrashDir> {
+    Vec::new()
+}
+
+/// Содержимое `files/`. Элемент без описания тоже попадает в список: восстановить его уже нельзя, только удалить
+pub fn entries(trash: &TrashDir) -> io::Result<Vec<TrashEntry>> {
+    let files = match std::fs::read_dir(trash.files_dir()) {
+        Ok(files) => files,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let info_dir = trash.info_dir();
+    let mut entries = Vec::new();
+    for file in files {
+        let file = file?;
+        let name = file.file_name();
+        let info_path = info_path(&info_dir, &name);
+
+        let info_modified = std::fs::metadata(&info_path).and_then(|metadata| metadata.modified()).ok();
+        let deletion_date = std::fs::read_to_string(&info_path)
+            .ok()
+            .and_then(|info| parse_deletion_date(&info));
+
+        entries.push(TrashEntry {
+            path: file.path(),
+            name,
+            deleted_at: deletion_date.or(info_modified),
+            info_modified: info_modified.map(seconds),
+        });
+    }
+    Ok(entries)
+}
+
+/// Является ли путь элементом `files/` какой-либо корзины
+pub fn is_entry(path: &Path) -> bool {
+    entry_parts(path).is_some()
+}
+
+/// Удаляет описания в `info/`, для которых нет записи в `files/`: восстановить по ним нечего.
+/// Свежие описания не трогаются, см. [`ORPHANED_INFO_AGE`]
+pub fn remove_orphaned_info(trash: &TrashDir) -> io::Result<usize> {
+    let infos = match std::fs::read_dir(trash.info_dir()) {
+        Ok(infos) => infos,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let files_dir = trash.files_dir();
+    let older_than = SystemTime::now() - ORPHANED_INFO_AGE;
+    let mut removed = 0;
+    for info in infos {
+        let info = info?;
+        let info_path = info.path();
+        let name = match info_path.file_stem() {
+            Some(name) if info_path.extension() == Some(OsStr::new(INFO_EXTENSION)) => name,
+            _ => continue,
+        };
+        if std::fs::symlink_metadata(files_dir.join(name)).is_ok() {
+            continue;
+        }
+
+        let is_old = info.metadata()?.modified().is_ok_and(|modified| modified < older_than);
+        if is_old {
+            std::fs::remove_file(&info_path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Кеши `directorysizes` корзин, которых касается очистка. Каждый читается при первом обращении
+/// и записывается один раз в [`SizeCaches::save`], а не на каждый элемент
+#[derive(Debug, Default)]
+pub struct SizeCaches {
+    caches: HashMap<PathBuf, DirectorySizes>,
+}
+
+impl SizeCaches {
+    fn cache(&mut self, root: &Path) -> &mut DirectorySizes {
+        self.caches
+            .entry(root.to_path_buf())
+            .or_insert_with(|| DirectorySizes::load(root))
+    }
+
+    /// Размер каталога корзины из `directorysizes`, если запись кеша ещё верна
+    pub fn cached_size(&mut self, path: &Path) -> Option<u64> {
+        let (root, name) = entry_parts(path)?;
+        let info_modified = std::fs::metadata(info_path(&root.join(INFO_DIR), name)).ok()?.modified().ok()?;
+        self.cache(root).lookup(name, Some(seconds(info_modified)))
+    }
+
+    /// Убирает описание удалённого элемента корзины и его запись в `directorysizes`.
+    /// Вызывается после того, как сам элемент убран из `files/`: так прерванная очистка не оставит описаний без файлов
+    pub fn forget(&mut self, path: &Path) -> io::Result<()> {
+        let (root, name) = entry_parts(path).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "путь вне корзины"))?;
+
+        match std::fs::remove_file(info_path(&root.join(INFO_DIR), name)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let sizes = self.cache(root);
+        if sizes.entries.remove(name).is_some() {
+            sizes.changed = true;
+        }
+        Ok(())
+    }
+
+    /// Запоминает размер каталога, только что перемещённого в корзину
+    pub fn record_dir_size(&mut self, trashed: &Path, size: u64) -> io::Result<()> {
+        let (root, name) = entry_parts(trashed).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "путь вне корзины"))?;
+        let info_modified = std::fs::metadata(info_path(&root.join(INFO_DIR), name))?.modified()?;
+
+        let sizes = self.cache(root);
+        sizes.entries.insert(name.to_os_string(), (size, seconds(info_modified)));
+        sizes.changed = true;
+        Ok(())
+    }
+
+    /// Записывает изменившиеся кеши. Запись, оставшаяся от удалённого элемента, безвредна:
+    /// по ней ничего не найдётся, поэтому ошибка одного кеша не мешает записать остальные
+    pub fn save(&self) -> io::Result<()> {
+        let mut result = Ok(());
+        for sizes in self.caches.values().filter(|sizes| sizes.changed) {
+            if let Err(e) = sizes.save() {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+/// Кеш `directorysizes`: размеры каталогов из `files/`, чтобы не обходить их заново.
+/// Размер считается как у `du -B1`; запись верна, пока время изменения описания совпадает с сохранённым
+#[derive(Debug, Default)]
+pub struct DirectorySizes {
+    path: PathBuf,
+    entries: HashMap<OsString, (u64, u64)>,
+    /// Записи менялись после загрузки
+    changed: bool,
+}
+
+impl DirectorySizes {
+    pub fn load(trash_root: &Path) -> Self {
+        let path = trash_root.join(DIRECTORY_SIZES);
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+
+        // Строка: «размер время_описания имя_в_процентной_кодировке»
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let size = fields.next()?.parse().ok()?;
+                let modified = fields.next()?.parse().ok()?;
+                let name = decode_name(fields.next()?)?;
+                Some((name, (size, modified)))
+            })
+            .collect();
+
+        Self {
+            path,
+            entries,
+            changed: false,
+        }
+    }
+
+    pub fn size_of(&self, entry: &TrashEntry) -> Option<u64> {
+        self.lookup(&entry.name, entry.info_modified)
+    }
+
+    fn lookup(&self, name: &OsStr, info_modified: Option<u64>) -> Option<u64> {
+        match self.entries.get(name) {
+            Some(&(size, modified)) if Some(modified) == info_modified => Some(size),
+            _ => None,
+        }
+    }
+
+    /// Перезаписывает кеш атомарно: через временный файл и переименование
+    fn save(&self) -> io::Result<()> {
+        let mut names: Vec<&OsString> = self.entries.keys().collect();
+        names.sort();
+
+        let mut content = String::new();
+        for name in names {
+            let (size, modified) = self.entries[name];
+            content.push_str(&format!("{} {} {}\n", size, modified, encode_path(Path::new(name))));
+        }
+
+        let temp = self.path.with_file_name(format!(".{}.tmp", DIRECTORY_SIZES));
+        std::fs::write(&temp, content)?;
+        std::fs::rename(&temp, &self.path)
+    }
+}
+
+/// Перемещает файл или каталог в корзину того раздела, на котором он лежит, и возвращает новый путь.
+/// Сначала атомарно создаётся описание в `info/`: его имя резервирует имя в `files/`
+#[cfg(unix)]
+pub fn move_to_trash(path: &Path, mounts: &MountTable) -> io::Result<PathBuf> {
+    let trash = trash_for(path, mounts)?;
+    let files_dir = trash.files_dir();
+    let info_dir = trash.info_dir();
+    create_private_dir(&files_dir)?;
+    create_private_dir(&info_dir)?;
+
+    let original = match &trash.topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(path),
+        None => path,
+    };
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original),
+        Local::now().format(DELETION_DATE_FORMAT)
+    );
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "путь без имени файла"))?;
+
+    for attempt in 1.. {
+        let trashed_name = numbered_name(Path::new(name), attempt);
+        let trashed = files_dir.join(&trashed_name);
+        if std::fs::symlink_metadata(&trashed).is_ok() {
+            continue;
+        }
+
+        let info_path = info_path(&info_dir, trashed_name.as_os_str());
+        let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+
+        let moved = info_file
+            .write_all(info.as_bytes())
+            .and_then(|_| std::fs::rename(path, &trashed));
+        if let Err(e) = moved {
+            let _ = std::fs::remove_file(&info_path);
+            return Err(e);
+        }
+        return Ok(trashed);
+    }
+
+    unreachable!()
+}
+
+#[cfg(not(unix))]
+pub fn move_to_trash(_path: &Path, _mounts: &MountTable) -> io::Result<PathBuf> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "корзина freedesktop.org доступна только в unix-системах",
+    ))
+}
+
+/// Файл перемещается только в пределах своего раздела: в домашнюю корзину, если она на том же разделе,
+/// иначе в `$topdir/.Trash/$uid` (если администратор создал `.Trash` с sticky-битом) или в `$topdir/.Trash-$uid`
+#[cfg(unix)]
+fn trash_for(path: &Path, mounts: &MountTable) -> io::Result<TrashDir> {
+    let home = home_trash().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "не найден домашний каталог"))?;
+
+    let mount = mounts.find(path);
+    let home_mount = mounts.find(&home.root);
+    let topdir = match mount {
+        Some(mount) if home_mount.map(|home_mount| &home_mount.mount_point) != Some(&mount.mount_point) => {
+            mount.mount_point.clone()
+        }
+        // Тот же раздел, что и у домашней корзины, или таблица монтирования недоступна
+        _ => return Ok(home),
+    };
+
+    let uid = current_uid().ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "неизвестен пользователь"))?;
+    if let Some(shared) = shared_trash(&topdir) {
+        let root = shared.join(uid.to_string());
+        if create_private_dir(&root).is_ok() {
+            return Ok(TrashDir {
+                root,
+                topdir: Some(topdir),
+            });
+        }
+    }
+
+    let root = topdir.join(format!(".Trash-{}", uid));
+    create_private_dir(&root)?;
+    Ok(TrashDir {
+        root,
+        topdir: Some(topdir),
+    })
+}
+
+/// Общий `$topdir/.Trash`, если он годится по спецификации: настоящий каталог с sticky-битом
+#[cfg(unix)]
+fn shared_trash(topdir: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let shared = topdir.join(".Trash");
+    let metadata = std::fs::symlink_metadata(&shared).ok()?;
+    (metadata.is_dir() && metadata.permissions().mode() & STICKY_BIT != 0).then_some(shared)
+}
+
+/// Создаёт каталог корзины с правами 700. Существующий каталог принимается, только если это не символическая ссылка
+#[cfg(unix)]
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    match std::fs::DirBuilder::new().recursive(true).mode(0o700).create(path) {
+        Ok(()) if std::fs::symlink_metadata(path)?.is_dir() => Ok(()),
+        Ok(()) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} не является каталогом", path.display()),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Корень корзины и имя элемента для пути вида `<корзина>/files/<имя>`
+fn entry_parts(path: &Path) -> Option<(&Path, &OsStr)> {
+    let name = path.file_name()?;
+    let files_dir = path.parent()?;
+    if files_dir.file_name()? != FILES_DIR {
+        return None;
+    }
+    Some((files_dir.parent()?, name))
+}
+
+fn info_path(info_dir: &Path, name: &OsStr) -> PathBuf {
+    let mut info_name = name.to_os_string();
+    info_name.push(".");
+    info_name.push(INFO_EXTENSION);
+    info_dir.join(info_name)
+}
+
+/// `DeletionDate` из группы `[Trash Info]`; дата записана в местном времени без часового пояса
+fn parse_deletion_date(info: &str) -> Option<SystemTime> {
+    let mut in_group = false;
+    for line in info.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_group = line == "[Trash Info]";
+        } else if let Some(value) = line.strip_prefix("DeletionDate=").filter(|_| in_group) {
+            let date = NaiveDateTime::parse_from_str(value.trim(), DELETION_DATE_FORMAT).ok()?;
+            return Local.from_local_datetime(&date).earliest().map(SystemTime::from);
+        }
+    }
+    None
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0)).as_secs()
+}
+
+/// `report.pdf`, `report.2.pdf`, `report.3.pdf`, …
+#[cfg(unix)]
+fn numbered_name(name: &Path, attempt: u32) -> PathBuf {
+    if attempt == 1 {
+        return name.to_path_buf();
+    }
+    match (name.file_stem(), name.extension()) {
+        (Some(stem), Some(extension)) => {
+            let mut numbered = stem.to_os_string();
+            numbered.push(format!(".{}.", attempt));
+            numbered.push(extension);
+            PathBuf::from(numbered)
+        }
+        _ => {
+            let mut numbered = name.as_os_str().to_os_string();
+            numbered.push(format!(".{}", attempt));
+            PathBuf::from(numbered)
+        }
+    }
+}
+
+/// Кодирует путь для строки `Path=` и `directorysizes` как URI (RFC 2396): всё, кроме безопасных символов и `/`, — в `%XX`
+#[cfg(unix)]
+fn encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(not(unix))]
+fn encode_path(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(unix)]
+fn decode_name(encoded: &str) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        if bytes[position] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(position + 1..position + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            position += 3;
+        } else {
+            decoded.push(bytes[position]);
+            position += 1;
+        }
+    }
+    Some(OsString::from_vec(decoded))
+}
+
+#[cfg(not(unix))]
+fn decode_name(encoded: &str) -> Option<OsString> {
+    Some(OsString::from(encoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_trash(name: &str) -> TrashDir {
+        let root = std::env::temp_dir().join(format!("trash-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let trash = TrashDir { root, topdir: None };
+        std::fs::create_dir_all(trash.files_dir()).unwrap();
+        std::fs::create_dir_all(trash.info_dir()).unwrap();
+        trash
+    }
+
+    #[test]
+    fn deletion_date_is_read_from_trash_info_group() {
+        let expected = Local.with_ymd_and_hms(2024, 3, 1, 12, 30, 5).unwrap();
+        let info = "[Trash Info]\nPath=/home/user/a%20b\nDeletionDate=2024-03-01T12:30:05\n";
+        assert_eq!(parse_deletion_date(info), Some(SystemTime::from(expected)));
+
+        // Дата в чужой группе и испорченная дата не считаются
+        assert_eq!(parse_deletion_date("[Other]\nDeletionDate=2024-03-01T12:30:05\n"), None);
+        assert_eq!(parse_deletion_date("[Trash Info]\nDeletionDate=yesterday\n"), None);
+        assert_eq!(parse_deletion_date(""), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_survive_percent_encoding() {
+        let name = OsString::from("report 100%.pdf");
+        let encoded = encode_path(Path::new(&name));
+        assert_eq!(encoded, "report%20100%25.pdf");
+        assert_eq!(decode_name(&encoded), Some(name));
+
+        assert_eq!(decode_name("%D0%B0"), Some(OsString::from("а")));
+        assert_eq!(decode_name("broken%2"), None);
+        assert_eq!(decode_name("broken%zz"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn orphaned_info_is_removed_once_settled() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let trash = temp_trash("orphans");
+        std::fs::write(trash.files_dir().join("kept.txt"), b"kept").unwrap();
+        for name in ["kept.txt", "gone.txt", "fresh.txt"] {
+            std::fs::write(info_path(&trash.info_dir(), OsStr::new(name)), "[Trash Info]\n").unwrap();
+        }
+        for name in ["kept.txt", "gone.txt"] {
+            let path = info_path(&trash.info_dir(), OsStr::new(name));
+            let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+            let times = [libc::timeval { tv_sec: 1_500_000_000, tv_usec: 0 }; 2];
+            // SAFETY: путь — корректная C-строка, массив содержит ровно два значения времени
+            assert_eq!(unsafe { libc::utimes(path.as_ptr(), times.as_ptr()) }, 0);
+        }
+
+        assert_eq!(remove_orphaned_info(&trash).unwrap(), 1);
+        let mut infos: Vec<OsString> = std::fs::read_dir(trash.info_dir())
+            .unwrap()
+            .map(|info| info.unwrap().file_name())
+            .collect();
+        infos.sort();
+        assert_eq!(infos, ["fresh.txt.trashinfo", "kept.txt.trashinfo"]);
+
+        std::fs::remove_dir_all(&trash.root).unwrap();
+    }
+
+    #[test]
+    fn size_caches_save_once_per_root() {
+        let trash = temp_trash("sizes");
+        let trashed = trash.files_dir().join("dir");
+        std::fs::create_dir(&trashed).unwrap();
+        std::fs::write(info_path(&trash.info_dir(), OsStr::new("dir")), "[Trash Info]\n").unwrap();
+
+        let mut caches = SizeCaches::default();
+        caches.record_dir_size(&trashed, 4096).unwrap();
+        assert_eq!(caches.cached_size(&trashed), Some(4096));
+        // До сохранения файл кеша не пишется
+        assert!(!trash.root.join(DIRECTORY_SIZES).exists());
+        caches.save().unwrap();
+        assert_eq!(DirectorySizes::load(&trash.root).entries.len(), 1);
+
+        std::fs::remove_dir(&trashed).unwrap();
+        caches.forget(&trashed).unwrap();
+        caches.save().unwrap();
+        assert!(DirectorySizes::load(&trash.root).entries.is_empty());
+        assert!(!info_path(&trash.info_dir(), OsStr::new("dir")).exists());
+
+        std::fs::remove_dir_all(&trash.root).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+use crate::cleaner::{file_id, FileId};
+use rayon::Scope;
+use std::fs::Metadata;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Элемент дерева, найденный при обходе
+pub struct WalkEntry {
+    pub path: PathBuf,
+    pub depth: usize,
+    /// Для раскрытой символической ссылки — метаданные цели
+    pub metadata: Metadata,
+    pub is_symlink: bool,
+    /// Каталог уже открыт выше по пути от корня (ссылка или bind-монтирование на предка).
+    /// В такой каталог обход не спускается, что бы ни ответил посетитель
+    pub is_loop: bool,
+}
+
+/// Решение посетителя: спускаться ли в каталог
+pub enum Visit {
+    Continue,
+    SkipDir,
+}
+
+/// Содержимое каталога, сохранённое с прошлого обхода
+pub struct CachedDir<T> {
+    /// Находки по файлам каталога (без подкаталогов)
+    pub found: Vec<T>,
+    /// Все подкаталоги; они посещаются заново, потому что могли измениться сами
+    pub subdirs: Vec<PathBuf>,
+}
+
+/// Кеш содержимого каталогов между обходами
+pub trait DirCache<T>: Sync {
+    /// Находки из прошлого обхода, если каталог с тех пор не менялся
+    fn lookup(&self, dir: &Path, metadata: &Metadata) -> Option<CachedDir<T>>;
+
+    /// Запоминает находки по файлам прочитанного каталога и возвращает то, что пойдёт в результат
+    fn store(&self, dir: &Path, metadata: &Metadata, found: Vec<T>, subdirs: Vec<PathBuf>) -> Vec<T>;
+}
+
+/// Обход без кеша: каждый каталог читается с диска
+pub struct NoCache;
+
+impl<T> DirCache<T> for NoCache {
+    fn lookup(&self, _dir: &Path, _metadata: &Metadata) -> Option<CachedDir<T>> {
+        None
+    }
+
+    fn store(&self, _dir: &Path, _metadata: &Metadata, found: Vec<T>, _subdirs: Vec<PathBuf>) -> Vec<T> {
+        found
+    }
+}
+
+/// Параллельно обходит дерево каталогов на текущем пуле rayon.
+///
+/// Каждый подкаталог обрабатывается отдельной задачей, поэтому большие деревья делятся между потоками.
+/// Посетитель вызывается для каждого элемента, включая сам корень (глубина 0), и складывает
+/// свои находки в переданный вектор. Символические ссылки внутри дерева не раскрываются.
+/// Каталог, совпадающий (устройство и inode) с одним из своих предков, помечается `is_loop` и не обходится.
+/// Каталог, который не удалось прочитать, передаётся в `error` вместе с ошибкой
+pub fn walk<T, F, E>(root: &Path, max_depth: usize, visit: F, error: E) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&WalkEntry, &mut Vec<T>) -> Visit + Sync,
+    E: Fn(&Path, &io::Error, &mut Vec<T>) + Sync,
+{
+    walk_cached(root, max_depth, false, visit, error, &NoCache)
+}
+
+/// То же, что [`walk`], но неизменившиеся каталоги не читаются: их находки берутся из `cache`,
+/// а посетитель вызывается только для подкаталогов. С `follow_links` символические ссылки
+/// раскрываются, и ссылки на каталоги обходятся как обычные подкаталоги
+pub fn walk_cached<T, F, E, C>(root: &Path, max_depth: usize, follow_links: bool, visit: F, error: E, cache: &C) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&WalkEntry, &mut Vec<T>) -> Visit + Sync,
+    E: Fn(&Path, &io::Error, &mut Vec<T>) + Sync,
+    C: DirCache<T>,
+{
+    let results = Mutex::new(Vec::new());
+
+    let metadata = match std::fs::metadata(root) {
+        Ok(m) => m,
+        Err(_) => return Vec::new(),
+    };
+
+    let entry = WalkEntry {
+        path: root.to_path_buf(),
+        depth: 0,
+        metadata,
+        is_symlink: false,
+        is_loop: false,
+    };
+
+    let mut found = Vec::new();
+    let descend = matches!(visit(&entry, &mut found), Visit::Continue)
+        && entry.metadata.is_dir()
+        && max_depth > 0;
+    results.lock().unwrap().extend(found);
+
+    if descend {
+        let walker = Walker {
+            max_depth,
+            follow_links,
+            visit: &visit,
+            error: &error,
+            cache,
+            results: &results,
+        };
+        rayon::scope(|scope| walker.walk_dir(scope, entry.path, entry.metadata, 1, None));
+    }
+
+    results.into_inner().unwrap()
+}
+
+/// Каталоги на пути от корня обхода до текущего. Задачи подкаталогов делят общее начало цепочки
+struct Ancestors {
+    id: FileId,
+    parent: Option<Arc<Ancestors>>,
+}
+
+impl Ancestors {
+    fn contains(&self, id: FileId) -> bool {
+        let mut ancestor = Some(self);
+        while let Some(current) = ancestor {
+            if current.id == id {
+                return true;
+            }
+            ancestor = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// Общие для всех задач обхода параметры
+struct Walker<'a, T, F, E, C> {
+    max_depth: usize,
+    follow_links: bool,
+    visit: &'a F,
+    error: &'a E,
+    cache: &'a C,
+    results: &'a Mutex<Vec<T>>,
+}
+
+impl<'a, T, F, E, C> Walker<'a, T, F, E, C>
+where
+    T: Send,
+    F: Fn(&WalkEntry, &mut Vec<T>) -> Visit + Sync,
+    E: Fn(&Path, &io::Error, &mut Vec<T>) + Sync,
+    C: DirCache<T>,
+{
+    fn walk_dir(&self, scope: &Scope<'a>, dir: PathBuf, metadata: Metadata, depth: usize, ancestors: Option<Arc<Ancestors>>) {
+        // Без идентификатора файла (не Unix) каталог в цепочку не попадает, и петли не распознаются
+        let ancestors = match file_id(&metadata) {
+            Some(id) => Some(Arc::new(Ancestors { id, parent: ancestors })),
+            None => ancestors,
+        };
+
+        // Находки копятся локально и сливаются один раз на каталог, чтобы не дёргать блокировку на каждый файл
+        let mut found = Vec::new();
+
+        if let Some(cached) = self.cache.lookup(&dir, &metadata) {
+            for subdir in cached.subdirs {
+                let metadata = match std::fs::symlink_metadata(&subdir) {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+                let entry = self.entry(subdir, depth, metadata, ancestors.as_deref());
+                if entry.metadata.is_dir() {
+                    self.visit_child(scope, entry, &ancestors, &mut found);
+                }
+            }
+            found.extend(cached.found);
+        } else {
+            let read_dir = match std::fs::read_dir(&dir) {
+                Ok(r) => r,
+                Err(e) => {
+                    let mut errors = Vec::new();
+                    (self.error)(&dir, &e, &mut errors);
+                    self.results.lock().unwrap().extend(errors);
+                    return;
+                }
+            };
+
+            let mut file_found = Vec::new();
+            let mut subdirs = Vec::new();
+
+            for entry in read_dir.flatten() {
+                // DirEntry::metadata не следует по символическим ссылкам
+                let metadata = match entry.metadata() {
+                    Ok(m) => m,
+                    Err(_) => continue,
+                };
+
+                let entry = self.entry(entry.path(), depth, metadata, ancestors.as_deref());
+                if entry.metadata.is_dir() {
+                    subdirs.push(entry.path.clone());
+                    self.visit_child(scope, entry, &ancestors, &mut found);
+                } else {
+                    (self.visit)(&entry, &mut file_found);
+                }
+            }
+
+            found.extend(self.cache.store(&dir, &metadata, file_found, subdirs));
+        }
+
+        if !found.is_empty() {
+            self.results.lock().unwrap().extend(found);
+        }
+    }
+
+    /// Раскрывает символическую ссылку, если это разрешено. Битая ссылка остаётся ссылкой
+    fn entry(&self, path: PathBuf, depth: usize, metadata: Metadata, ancestors: Option<&Ancestors>) -> WalkEntry {
+        let is_symlink = metadata.file_type().is_symlink();
+        let metadata = if is_symlink && self.follow_links {
+            std::fs::metadata(&path).unwrap_or(metadata)
+        } else {
+            metadata
+        };
+        let is_loop = metadata.is_dir()
+            && file_id(&metadata).is_some_and(|id| ancestors.is_some_and(|ancestors| ancestors.contains(id)));
+
+        WalkEntry {
+            path,
+            depth,
+            metadata,
+            is_symlink,
+            is_loop,
+        }
+    }
+
+    fn visit_child(&self, scope: &Scope<'a>, entry: WalkEntry, ancestors: &Option<Arc<Ancestors>>, found: &mut Vec<T>) {
+        let descend = matches!((self.visit)(&entry, found), Visit::Continue)
+            && !entry.is_loop
+            && entry.depth < self.max_depth;
+
+        if descend {
+            let walker = Walker { ..*self };
+            let ancestors = ancestors.clone();
+            scope.spawn(move |scope| walker.walk_dir(scope, entry.path, entry.metadata, entry.depth + 1, ancestors));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 import React, { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
 import { getCurrent } from '@tauri-apps/api/window'
 import { 
   Trash2, 
@@ -19,11 +20,54 @@ interface ScanResult {
   total_files: number
   total_size: number
   files: FileInfo[]
+  errors: string[]
+  skipped_by_size: number
+  skipped_by_size_bytes: number
+  overlaps: CategoryOverlap[]
+  cut_off: CutOff[]
+  duplicates: DuplicateSet[]
+  mount: string | null
+}
+
+interface MountUsage {
+  mount_point: string
+  device: string
+  fs_type: string
+  total: number
+  used: number
+  free: number
+  read_only: boolean
+  removable: boolean
+}
+
+interface DuplicateSet {
+  size: number
+  hash: string
+  files: (FileInfo & { action: 'keep' | 'removable' })[]
+}
+
+interface FileId {
+  device: number
+  inode: number
+}
+
+interface CategoryOverlap {
+  owner: string
+  files: number
+  size: number
+}
+
+interface CutOff {
+  path: string
+  reason: 'max_depth' | 'symlink' | 'symlink_loop' | 'other_filesystem'
 }
 
 interface FileInfo {
   path: string
   size: number
+  allocated: number
+  file_id: FileId | null
+  links: number
   modified: number
   is_directory: boolean
 }
@@ -31,8 +75,27 @@ interface FileInfo {
 interface CleanResult {
   category: string
   files_removed: number
+  files_linked: number
+  files_trashed: number
   space_freed: number
+  space_trashed: number
+  space_quarantined: number
+  dirs_pruned: number
   errors: string[]
+  skipped: SkippedFile[]
+  dry_run: boolean
+  would_remove: FileInfo[]
+  warnings: string[]
+}
+
+interface ScanTotals {
+  total_files: number
+  total_size: number
+}
+
+interface SkippedFile {
+  path: string
+  reason: string
 }
 
 const CATEGORY_NAMES: Record<string, string> = {
@@ -43,6 +106,7 @@ const CATEGORY_NAMES: Record<string, string> = {
   windows_cache: 'Кеш Windows',
   thumbnails: 'Миниатюры',
   old_downloads: 'Старые загрузки',
+  duplicates: 'Дубликаты',
   app_cache: 'Кеш приложений',
   installers: 'Установщики',
   windows_update: 'Windows Update',
@@ -64,7 +128,11 @@ const CATEGORY_NAMES: Record<string, string> = {
   games_cache: 'Игровой кеш',
   system_utils: 'Системные утилиты',
   archivers_cache: 'Архиваторы',
-  cloud_cache: 'Облачные хранилища'
+  cloud_cache: 'Облачные хранилища',
+  xdg_cache: 'Кеш пользователя',
+  xsession_errors: 'Журнал X-сессии',
+  user_journals: 'Журналы systemd',
+  core_dumps: 'Дампы памяти'
 }
 
 const CATEGORY_ICONS: Record<string, React.ReactNode> = {
@@ -75,6 +143,7 @@ const CATEGORY_ICONS: Record<string, React.ReactNode> = {
   windows_cache: <Settings className="w-4 h-4" />,
   thumbnails: <HardDrive className="w-4 h-4" />,
   old_downloads: <HardDrive className="w-4 h-4" />,
+  duplicates: <HardDrive className="w-4 h-4" />,
   app_cache: <Settings className="w-4 h-4" />,
   installers: <Settings className="w-4 h-4" />,
   windows_update: <Settings className="w-4 h-4" />,
@@ -96,7 +165,52 @@ const CATEGORY_ICONS: Record<string, React.ReactNode> = {
   games_cache: <Settings className="w-4 h-4" />,
   system_utils: <Settings className="w-4 h-4" />,
   archivers_cache: <Settings className="w-4 h-4" />,
-  cloud_cache: <Settings className="w-4 h-4" />
+  cloud_cache: <Settings className="w-4 h-4" />,
+  xdg_cache: <Settings className="w-4 h-4" />,
+  xsession_errors: <Settings className="w-4 h-4" />,
+  user_journals: <Settings className="w-4 h-4" />,
+  core_dumps: <Settings className="w-4 h-4" />
+}
+
+interface JobStatus {
+  id: string
+  kind: 'scan' | 'clean' | 'analyze'
+  state: 'running' | 'completed' | 'cancelled' | 'failed'
+  progress: number
+  error: string | null
+  started_at: string
+  finished_at: string | null
+}
+
+interface ProgressEvent {
+  job_id: string
+  kind: 'scan' | 'clean' | 'analyze'
+  state: JobStatus['state']
+  category: string | null
+  current_dir: string | null
+  files_seen: number
+  files_matched: number
+  bytes_matched: number
+  errors: number
+  progress: number
+  eta_seconds: number | null
+}
+
+// Запускает фоновую задачу и ждёт её завершения
+async function runJob<T>(command: string, args?: Record<string, unknown>): Promise<{ status: JobStatus, result: T | null }> {
+  const jobId = await invoke<string>(command, args)
+
+  for (;;) {
+    const status = await invoke<JobStatus>('get_job_status', { jobId })
+    if (status.state !== 'running') {
+      if (status.state === 'failed') {
+        throw status.error
+      }
+      const result = await invoke<T | null>('get_job_result', { jobId })
+      return { status, result }
+    }
+    await new Promise(resolve => setTimeout(resolve, 100))
+  }
 }
 
 function formatBytes(bytes: number): string {
@@ -109,54 +223,60 @@ function formatBytes(bytes: number): string {
 
 function App() {
   const [scanResults, setScanResults] = useState<Record<string, ScanResult>>({})
+  const [scanTotals, setScanTotals] = useState<ScanTotals>({ total_files: 0, total_size: 0 })
+  const [mounts, setMounts] = useState<Record<string, MountUsage>>({})
   const [cleanResults, setCleanResults] = useState<CleanResult[]>([])
   const [isScanning, setIsScanning] = useState(false)
   const [isCleaning, setIsCleaning] = useState(false)
   const [scanProgress, setScanProgress] = useState(0)
   const [cleanProgress, setCleanProgress] = useState(0)
+  const [progressEvent, setProgressEvent] = useState<ProgressEvent | null>(null)
   const [selectedCategories, setSelectedCategories] = useState<string[]>([])
   const [error, setError] = useState<string | null>(null)
   const [success, setSuccess] = useState<string | null>(null)
 
-  const totalSize = Object.values(scanResults).reduce((sum, result) => sum + result.total_size, 0)
-  const totalFiles = Object.values(scanResults).reduce((sum, result) => sum + result.total_files, 0)
+  // Суммы считает бэкенд: жёсткие ссылки на один файл из разных категорий учитываются один раз
+  const totalSize = scanTotals.total_size
+  const totalFiles = scanTotals.total_files
 
   useEffect(() => {
-    const interval = setInterval(async () => {
-      if (isScanning) {
-        try {
-          const progress = await invoke<number>('get_scan_progress')
-          setScanProgress(progress * 100)
-        } catch (e) {
-          console.error('Failed to get scan progress:', e)
-        }
-      }
-      if (isCleaning) {
-        try {
-          const progress = await invoke<number>('get_clean_progress')
-          setCleanProgress(progress * 100)
-        } catch (e) {
-          console.error('Failed to get clean progress:', e)
-        }
+    const unlisten = listen<ProgressEvent>('job-progress', ({ payload }) => {
+      if (payload.kind === 'scan') {
+        setScanProgress(payload.progress * 100)
+      } else if (payload.kind === 'clean') {
+        setCleanProgress(payload.progress * 100)
+      } else {
+        return
       }
-    }, 100)
+      setProgressEvent(payload)
+    })
 
-    return () => clearInterval(interval)
-  }, [isScanning, isCleaning])
+    return () => {
+      unlisten.then(stop => stop())
+    }
+  }, [])
 
   const handleScan = async () => {
     setIsScanning(true)
     setError(null)
     setSuccess(null)
     setScanProgress(0)
+    setProgressEvent(null)
     
     // Расширяем окно при сканировании
     await expandWindow()
     
     try {
-      const results = await invoke<Record<string, ScanResult>>('scan_system')
+      const { result: results } = await runJob<Record<string, ScanResult>>('scan_system')
+      if (!results) {
+        setSuccess('Сканирование отменено')
+        return
+      }
       setScanResults(results)
+      setScanTotals(await invoke<ScanTotals>('get_scan_totals'))
       setSelectedCategories(Object.keys(results))
+      const mountList = await invoke<MountUsage[]>('get_mounts')
+      setMounts(Object.fromEntries(mountList.map(mount => [mount.mount_point, mount])))
       setSuccess('Сканирование завершено успешно!')
     } catch (e) {
       setError(`Ошибка сканирования: ${e}`)
@@ -175,17 +295,24 @@ function App() {
     setError(null)
     setSuccess(null)
     setCleanProgress(0)
+    setProgressEvent(null)
     
     try {
-      const results = await invoke<CleanResult[]>('clean_system', { 
+      const { status, result } = await runJob<CleanResult[]>('clean_system', { 
         categories: selectedCategories 
       })
+      const results = result ?? []
       setCleanResults(results)
       
       const totalFilesRemoved = results.reduce((sum, r) => sum + r.files_removed, 0)
       const totalSpaceFreed = results.reduce((sum, r) => sum + r.space_freed, 0)
+      const totalFilesTrashed = results.reduce((sum, r) => sum + r.files_trashed, 0)
+      const totalSpaceQuarantined = results.reduce((sum, r) => sum + r.space_quarantined, 0)
       
-      setSuccess(`Очистка завершена! Удалено файлов: ${totalFilesRemoved}, освобождено места: ${formatBytes(totalSpaceFreed)}`)
+      const title = status.state === 'cancelled' ? 'Очистка отменена' : 'Очистка завершена'
+      const trashed = totalFilesTrashed > 0 ? `, перемещено в корзину: ${totalFilesTrashed}` : ''
+      const quarantined = totalSpaceQuarantined > 0 ? `, в карантине: ${formatBytes(totalSpaceQuarantined)}` : ''
+      setSuccess(`${title}! Удалено файлов: ${totalFilesRemoved}${trashed}, освобождено места: ${formatBytes(totalSpaceFreed)}${quarantined}`)
       
       // Обновляем результаты сканирования
       await handleScan()
@@ -323,6 +450,16 @@ function App() {
                   style={{ width: `${scanProgress}%` }}
                 />
               </div>
+              {progressEvent?.kind === 'scan' && (
+                <div className="flex justify-between text-xs text-gray-500 mt-1">
+                  <span className="truncate mr-2">{progressEvent.current_dir ?? progressEvent.category}</span>
+                  <span className="whitespace-nowrap">
+                    Найдено: {progressEvent.files_matched} из {progressEvent.files_seen}, {formatBytes(progressEvent.bytes_matched)}
+                    {progressEvent.errors > 0 && `, ошибок: ${progressEvent.errors}`}
+                    {progressEvent.eta_seconds !== null && `, осталось ~${progressEvent.eta_seconds} с`}
+                  </span>
+                </div>
+              )}
             </div>
           )}
 
@@ -338,6 +475,16 @@ function App() {
                   style={{ width: `${cleanProgress}%` }}
                 />
               </div>
+              {progressEvent?.kind === 'clean' && (
+                <div className="flex justify-between text-xs text-gray-500 mt-1">
+                  <span className="truncate mr-2">{progressEvent.current_dir ?? progressEvent.category}</span>
+                  <span className="whitespace-nowrap">
+                    Удалено: {progressEvent.files_matched} из {progressEvent.files_seen}, {formatBytes(progressEvent.bytes_matched)}
+                    {progressEvent.errors > 0 && `, ошибок: ${progressEvent.errors}`}
+                    {progressEvent.eta_seconds !== null && `, осталось ~${progressEvent.eta_seconds} с`}
+                  </span>
+                </div>
+              )}
             </div>
           )}
         </div>
@@ -394,6 +541,14 @@ function App() {
                   <span className="text-gray-400">Размер:</span>
                   <span className="font-medium text-gray-200">{formatBytes(result.total_size)}</span>
                 </div>
+                {result.mount && mounts[result.mount] && (
+                  <div className="flex justify-between text-xs">
+                    <span className="text-gray-400">Свободно на {result.mount}:</span>
+                    <span className="font-medium text-gray-200">
+                      {formatBytes(mounts[result.mount].free)} из {formatBytes(mounts[result.mount].total)}
+                    </span>
+                  </div>
+                )}
               </div>
 
             </div>
@@ -425,7 +580,37 @@ function App() {
                       <span className="text-gray-400">Освобождено:</span>
                       <span className="ml-1 font-medium text-gray-200">{formatBytes(result.space_freed)}</span>
                     </div>
+                    {result.space_quarantined > 0 && (
+                      <div>
+                        <span className="text-gray-400">В карантине:</span>
+                        <span className="ml-1 font-medium text-gray-200">{formatBytes(result.space_quarantined)}</span>
+                      </div>
+                    )}
+                    {result.files_trashed > 0 && (
+                      <div>
+                        <span className="text-gray-400">В корзине:</span>
+                        <span className="ml-1 font-medium text-gray-200">
+                          {result.files_trashed} ({formatBytes(result.space_trashed)})
+                        </span>
+                      </div>
+                    )}
+                    {result.files_linked > 0 && (
+                      <div>
+                        <span className="text-gray-400">Заменено ссылками:</span>
+                        <span className="ml-1 font-medium text-gray-200">{result.files_linked}</span>
+                      </div>
+                    )}
                   </div>
+                  {result.warnings.length > 0 && (
+                    <div className="mt-2">
+                      <div className="text-xs text-yellow-400 font-medium">Предупреждения:</div>
+                      <ul className="text-xs text-yellow-300 mt-1">
+                        {result.warnings.map((warning, i) => (
+                          <li key={i}>• {warning}</li>
+                        ))}
+                      </ul>
+                    </div>
+                  )}
                   {result.errors.length > 0 && (
                     <div className="mt-2">
                       <div className="text-xs text-danger-400 font-medium">Ошибки:</div>